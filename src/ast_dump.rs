@@ -0,0 +1,335 @@
+/// Dump of the syntax tree for the `ast` command: as an indented tree, as JSON
+/// with node kinds and positions (also used by `parse --format json`), or as
+/// a Graphviz `dot` graph.
+///
+/// Every node has a kind (e.g. Binary), an optional label shown in the tree and
+/// graph (operator, name or literal), attributes written to JSON and the position
+/// (line, column) of its first token kept in the tree. Children are named after
+/// their role, e.g. `left` and `right` operands of Binary.
+use std::fmt::Write;
+
+use crate::{
+    json::Json,
+    lexer::LineNum,
+    parser::{Class, Decl, Expression, Function, Program, Stmt},
+    token::{Token, TokenType},
+};
+
+/// Child of a node: single node, list of nodes or nothing (e.g. if without else)
+enum Child {
+    Node(Node),
+    List(Vec<Node>),
+    Missing,
+}
+
+pub(crate) struct Node {
+    kind: &'static str,
+    label: Option<String>,
+    attrs: Vec<(&'static str, Json)>,
+    position: Option<(LineNum, usize)>,
+    children: Vec<(&'static str, Child)>,
+}
+
+impl Node {
+    fn new(kind: &'static str) -> Self {
+        Node {
+            kind,
+            label: None,
+            attrs: Vec::new(),
+            position: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// node labelled with token text, e.g. variable name, positioned at the token
+    fn named(kind: &'static str, key: &'static str, t: &Token) -> Self {
+        Node::new(kind).at(t).label(key, t.s.clone())
+    }
+
+    fn label(mut self, key: &'static str, text: String) -> Self {
+        self.attrs.push((key, text.as_str().into()));
+        self.label = Some(text);
+        self
+    }
+
+    fn attr(mut self, key: &'static str, value: Json) -> Self {
+        self.attrs.push((key, value));
+        self
+    }
+
+    /// position of token read from source
+    fn at(mut self, t: &Token) -> Self {
+        if t.span.is_known() {
+            self.position = Some((t.ln, t.span.col));
+        }
+        self
+    }
+
+    /// position of start of expression
+    fn at_expr(mut self, e: &Expression) -> Self {
+        if let (Some(line), Some(span)) = (e.line(), e.span()) {
+            self.position = Some((line, span.col)).filter(|_| span.is_known());
+        }
+        self
+    }
+
+    fn child(mut self, field: &'static str, node: Node) -> Self {
+        self.children.push((field, Child::Node(node)));
+        self
+    }
+
+    fn optional(mut self, field: &'static str, node: Option<Node>) -> Self {
+        self.children.push((field, node.map_or(Child::Missing, Child::Node)));
+        self
+    }
+
+    fn list(mut self, field: &'static str, nodes: Vec<Node>) -> Self {
+        self.children.push((field, Child::List(nodes)));
+        self
+    }
+
+    /// kind, label and position, e.g. `Binary + [1:3]`
+    fn heading(&self) -> String {
+        let mut s = self.kind.to_string();
+        if let Some(l) = &self.label {
+            let _ = write!(s, " {}", l);
+        }
+        if let Some((line, col)) = self.position {
+            let _ = write!(s, " [{}:{}]", line, col);
+        }
+        s
+    }
+
+    /// indented tree, one node per line
+    pub(crate) fn to_text(&self) -> String {
+        let mut out = self.heading();
+        out.push('\n');
+        self.write_children(&mut out, "");
+        out
+    }
+
+    fn write_children(&self, out: &mut String, prefix: &str) {
+        let children = self
+            .children
+            .iter()
+            .filter(|(_, c)| !matches!(c, Child::Missing))
+            .collect::<Vec<_>>();
+        for (i, (field, child)) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            match child {
+                Child::Node(n) => n.write_branch(out, prefix, last, &format!("{}: ", field)),
+                Child::List(v) if v.is_empty() => {
+                    branch(out, prefix, last, &format!("{}: (none)", field))
+                }
+                Child::List(v) => {
+                    branch(out, prefix, last, field);
+                    let inner = nested(prefix, last);
+                    for (j, n) in v.iter().enumerate() {
+                        n.write_branch(out, &inner, j + 1 == v.len(), "");
+                    }
+                }
+                Child::Missing => (),
+            }
+        }
+    }
+
+    /// writes heading (after field name) as branch of tree, then subtree below it
+    fn write_branch(&self, out: &mut String, prefix: &str, last: bool, field: &str) {
+        branch(out, prefix, last, &format!("{}{}", field, self.heading()));
+        self.write_children(out, &nested(prefix, last));
+    }
+
+    /// the same tree with positions of all nodes unknown, for comparing
+    /// syntax of sources laid out differently
+    #[cfg(test)]
+    pub(crate) fn without_positions(mut self) -> Self {
+        self.position = None;
+        for (_, child) in self.children.iter_mut() {
+            *child = match std::mem::replace(child, Child::Missing) {
+                Child::Node(n) => Child::Node(n.without_positions()),
+                Child::List(v) => Child::List(v.into_iter().map(Node::without_positions).collect()),
+                Child::Missing => Child::Missing,
+            };
+        }
+        self
+    }
+
+    /// object with kind, attributes, line, column and children by field name;
+    /// missing children are null
+    pub(crate) fn to_json(&self) -> Json {
+        let (line, column) = self.position.unzip();
+        let mut doc = Json::object([("kind", self.kind.into())]);
+        for (k, v) in self.attrs.iter() {
+            doc = doc.with(k, v.clone());
+        }
+        doc = doc.with("line", line.into()).with("column", column.into());
+        for (field, child) in self.children.iter() {
+            let value = match child {
+                Child::Node(n) => n.to_json(),
+                Child::List(v) => Json::Array(v.iter().map(Node::to_json).collect()),
+                Child::Missing => Json::Null,
+            };
+            doc = doc.with(field, value);
+        }
+        doc
+    }
+
+    /// Graphviz digraph; edges are labelled with field names
+    /// (and index for nodes in a list)
+    pub(crate) fn to_dot(&self) -> String {
+        let mut out = String::from("digraph ast {\n    node [shape=box, fontname=monospace];\n");
+        let mut next = 0;
+        self.write_dot(&mut out, &mut next);
+        out.push_str("}\n");
+        out
+    }
+
+    /// writes node and its subtree, returns id of the node
+    fn write_dot(&self, out: &mut String, next: &mut usize) -> usize {
+        let id = *next;
+        *next += 1;
+        let mut label = self.kind.to_string();
+        if let Some(l) = &self.label {
+            let _ = write!(label, "\n{}", l);
+        }
+        if let Some((line, col)) = self.position {
+            let _ = write!(label, "\n{}:{}", line, col);
+        }
+        let _ = writeln!(out, "    n{} [label={}];", id, dot_string(&label));
+        for (field, child) in self.children.iter() {
+            match child {
+                Child::Node(n) => {
+                    let child = n.write_dot(out, next);
+                    let edge = dot_string(field);
+                    let _ = writeln!(out, "    n{} -> n{} [label={}];", id, child, edge);
+                }
+                Child::List(v) => {
+                    for (i, n) in v.iter().enumerate() {
+                        let child = n.write_dot(out, next);
+                        let edge = dot_string(&format!("{}[{}]", field, i));
+                        let _ = writeln!(out, "    n{} -> n{} [label={}];", id, child, edge);
+                    }
+                }
+                Child::Missing => (),
+            }
+        }
+        id
+    }
+}
+
+/// line of tree text connected to its parent
+fn branch(out: &mut String, prefix: &str, last: bool, text: &str) {
+    let _ = writeln!(out, "{}{}{}", prefix, if last { "└── " } else { "├── " }, text);
+}
+
+/// prefix of lines below branch
+fn nested(prefix: &str, last: bool) -> String {
+    format!("{}{}", prefix, if last { "    " } else { "│   " })
+}
+
+/// quoted dot string; line breaks are written as `\n` escapes
+fn dot_string(s: &str) -> String {
+    let escaped = s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{}\"", escaped)
+}
+
+pub(crate) fn program(p: &Program) -> Node {
+    Node::new("Program").list("declarations", p.declarations.iter().map(decl).collect())
+}
+
+fn function(f: &Function) -> Node {
+    Node::named("Function", "name", &f.name)
+        .list("params", f.params.iter().map(|p| Node::named("Param", "name", p)).collect())
+        .list("body", f.body.iter().map(decl).collect())
+}
+
+fn class(c: &Class) -> Node {
+    Node::named("Class", "name", &c.name)
+        .optional("superclass", c.superclass.as_ref().map(expr))
+        .list("methods", c.methods.iter().map(|m| function(m)).collect())
+}
+
+fn decl(d: &Decl) -> Node {
+    match d {
+        Decl::VarDecl(t, e) => {
+            Node::named("Var", "name", t).optional("initializer", e.as_ref().map(expr))
+        }
+        Decl::Fun(f) => function(f),
+        Decl::Class(c) => class(c),
+        Decl::Statement(s) => stmt(s),
+    }
+}
+
+fn stmt(s: &Stmt) -> Node {
+    let labelled = |kind, l: &Option<Token>| match l {
+        Some(l) => Node::new(kind).label("label", l.s.clone()),
+        None => Node::new(kind).attr("label", Json::Null),
+    };
+    match s {
+        Stmt::Print(k, e) => Node::new("Print").at(k).child("expression", expr(e)),
+        Stmt::Expression(e) => Node::new("Expression").at_expr(e).child("expression", expr(e)),
+        Stmt::Block(v) => Node::new("Block").list("declarations", v.iter().map(decl).collect()),
+        Stmt::If(k, c, t, e) => Node::new("If")
+            .at(k)
+            .child("condition", expr(c))
+            .child("then", stmt(t))
+            .optional("else", e.as_ref().map(|e| stmt(e))),
+        Stmt::While(k, c, b, l) => labelled("While", l)
+            .at(k)
+            .child("condition", expr(c))
+            .child("body", stmt(b)),
+        Stmt::Break(k, l) => labelled("Break", l).at(k),
+        Stmt::Continue(k, l) => labelled("Continue", l).at(k),
+        Stmt::Return(k, e) => Node::new("Return").at(k).optional("value", e.as_ref().map(expr)),
+        Stmt::Invalid => Node::new("Invalid"),
+    }
+}
+
+fn expr(e: &Expression) -> Node {
+    let node = |kind| Node::new(kind).at_expr(e);
+    match e {
+        Expression::Primary(t) => {
+            let text = match &t.typ {
+                TokenType::StringLiteral => format!("\"{}\"", t.s),
+                _ => t.s.clone(),
+            };
+            let value = match &t.typ {
+                TokenType::True => Json::Bool(true),
+                TokenType::False => Json::Bool(false),
+                TokenType::Number(n) => Json::Number(n.0),
+                TokenType::StringLiteral => t.s.as_str().into(),
+                _ => Json::Null,
+            };
+            let mut n = node("Literal").attr("value", value);
+            n.label = Some(text);
+            n
+        }
+        Expression::BinaryEx(l, op, t, r) => Node::new("Binary")
+            .at(t)
+            .label("operator", op.to_string())
+            .child("left", expr(l))
+            .child("right", expr(r)),
+        Expression::LogicalEx(l, op, t, r) => Node::new("Logical")
+            .at(t)
+            .label("operator", op.to_string())
+            .child("left", expr(l))
+            .child("right", expr(r)),
+        Expression::Call(c, _, args) => node("Call")
+            .child("callee", expr(c))
+            .list("arguments", args.iter().map(expr).collect()),
+        Expression::Get(o, n) => node("Get").label("name", n.s.clone()).child("object", expr(o)),
+        Expression::Set(o, n, v) => node("Set")
+            .label("name", n.s.clone())
+            .child("object", expr(o))
+            .child("value", expr(v)),
+        Expression::This(t, _) => Node::new("This").at(t),
+        Expression::Super(t, m, _) => Node::new("Super").at(t).label("method", m.s.clone()),
+        Expression::UnaryEx(op, _, operand) => node("Unary")
+            .label("operator", op.to_string())
+            .child("operand", expr(operand)),
+        Expression::Paren(_, inner, _) => node("Grouping").child("expression", expr(inner)),
+        Expression::Variable(t, _) => Node::named("Variable", "name", t),
+        Expression::Assign(t, v, _) => Node::named("Assign", "name", t).child("value", expr(v)),
+        Expression::Invalid => Node::new("Invalid"),
+    }
+}
@@ -0,0 +1,447 @@
+/// Commands of the interpreter binary: each takes source code, writes its results
+/// to stdout/stderr and returns process exit code.
+use std::fmt::Display;
+use std::io::{stderr, stdout};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use crate::ast_dump;
+use crate::conformance;
+use crate::debugger::Debugger;
+use crate::diagnostics::{render, LexError, ParseError};
+use crate::evaluator::StatementEvalResult;
+use crate::interpreter::{check, Interpreter, PARSE_ERROR_CODE};
+use crate::json::{self, Json};
+use crate::lexer::Lexer;
+use crate::linter;
+use crate::parser::{self, Decl, Stmt};
+use crate::token::{Token, TokenType};
+use crate::utils::{contents, IO_ERROR_CODE};
+
+/// Output format of commands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// human readable text (default)
+    #[default]
+    Text,
+    /// one JSON object per command with results, diagnostics and exit code
+    Json,
+    /// Graphviz graph (ast command only)
+    Dot,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "dot" => Ok(Format::Dot),
+            other => Err(format!("Unknown format: {}", other)),
+        }
+    }
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Text => f.write_str("text"),
+            Format::Json => f.write_str("json"),
+            Format::Dot => f.write_str("dot"),
+        }
+    }
+}
+
+/// prints JSON document and returns its exit code
+fn print_json((doc, code): (Json, u8)) -> ExitCode {
+    println!("{}", doc);
+    ExitCode::from(code)
+}
+
+/// JSON document with given fields followed by diagnostics and exit code
+fn json_report<const N: usize>(
+    fields: [(&str, Json); N],
+    diagnostics: Vec<Json>,
+    code: u8,
+) -> (Json, u8) {
+    let doc = Json::object(fields)
+        .with("diagnostics", Json::Array(diagnostics))
+        .with("exit_code", code.into());
+    (doc, code)
+}
+
+pub(crate) fn tokenize_json(s: &str) -> (Json, u8) {
+    let tokens = Lexer::new(s).tokens();
+    let errors = tokens.iter().filter_map(LexError::from_token).collect::<Vec<_>>();
+    let valid = tokens.iter().filter(|t| !matches!(t.typ, TokenType::Unknown(_)));
+    let code = if errors.is_empty() { 0 } else { PARSE_ERROR_CODE };
+    json_report(
+        [("tokens", Json::Array(valid.map(json::token).collect()))],
+        errors.iter().map(json::lex_error).collect(),
+        code,
+    )
+}
+
+pub(crate) fn parse_json(s: &str) -> (Json, u8) {
+    let tokens = Lexer::new(s).tokens();
+    let mut diagnostics = tokens
+        .iter()
+        .filter_map(LexError::from_token)
+        .map(|e| json::lex_error(&e))
+        .collect::<Vec<_>>();
+    let prog = parser::Parser::new(tokens).parse();
+    diagnostics.extend(prog.syntax_errors().iter().map(json::parse_error));
+    let code = if diagnostics.is_empty() { 0 } else { PARSE_ERROR_CODE };
+    json_report([("ast", ast_dump::program(&prog).to_json())], diagnostics, code)
+}
+
+/// values of top level expression and print statements, and printed output
+pub(crate) fn evaluate_json(s: &str) -> (Json, u8) {
+    let mut out = Vec::new();
+    let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+    let res = interpreter.run_source(s);
+    let exit_status = interpreter.exit_status().unwrap_or(0);
+    drop(interpreter);
+    let output = String::from_utf8_lossy(&out).to_string();
+    match res {
+        Ok(results) => {
+            let values = results
+                .iter()
+                .filter_map(|r| match r {
+                    StatementEvalResult::ExpressionStatementResult(v)
+                    | StatementEvalResult::PrintStatementResult(v) => Some(json::value(v)),
+                    _ => None,
+                })
+                .collect();
+            let fields = [("results", Json::Array(values)), ("output", output.into())];
+            json_report(fields, Vec::new(), exit_status)
+        }
+        Err(e) => {
+            let fields = [("results", Json::Array(Vec::new())), ("output", output.into())];
+            json_report(fields, json::interpreter_error(&e), e.exit_code())
+        }
+    }
+}
+
+pub(crate) fn run_json(s: &str, args: &[String]) -> (Json, u8) {
+    let mut out = Vec::new();
+    let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+    interpreter.set_args(args.to_vec());
+    let res = interpreter.run_source(s);
+    let exit_status = interpreter.exit_status().unwrap_or(0);
+    drop(interpreter);
+    let output = String::from_utf8_lossy(&out).to_string();
+    match res {
+        Ok(_) => json_report([("output", output.into())], Vec::new(), exit_status),
+        Err(e) => json_report(
+            [("output", output.into())],
+            json::interpreter_error(&e),
+            e.exit_code(),
+        ),
+    }
+}
+
+pub(crate) fn ast_json(s: &str) -> (Json, u8) {
+    match check(s) {
+        Ok(prog) => json_report([("ast", ast_dump::program(&prog).to_json())], Vec::new(), 0),
+        Err(e) => json_report([("ast", Json::Null)], json::interpreter_error(&e), e.exit_code()),
+    }
+}
+
+/// Prints syntax tree of program as indented tree, JSON or Graphviz graph
+pub fn ast(s: &str, format: Format) -> ExitCode {
+    if format == Format::Json {
+        return print_json(ast_json(s));
+    }
+    match check(s) {
+        Ok(prog) => {
+            let tree = ast_dump::program(&prog);
+            match format {
+                Format::Dot => print!("{}", tree.to_dot()),
+                _ => print!("{}", tree.to_text()),
+            }
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e.render(s));
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+pub fn tokenize(s: &str, format: Format) -> ExitCode {
+    if format == Format::Json {
+        return print_json(tokenize_json(s));
+    }
+    let mut exit_code = ExitCode::SUCCESS;
+    for token in Lexer::new(s).tokens() {
+        match token.typ {
+            TokenType::Unknown(_) => {
+                eprintln!("{}", token);
+                exit_code = ExitCode::from(PARSE_ERROR_CODE);
+            }
+            _ => println!("{}", token),
+        }
+    }
+    exit_code
+}
+fn parse_with_code_and_errors(s: &str) -> (parser::Program, u8, Vec<ParseError>) {
+    let tokens: Vec<Token> = Lexer::new(s).tokens();
+
+    let mut parser = parser::Parser::new(tokens);
+    let prog = parser.parse();
+
+    let errors = prog.syntax_errors().to_vec();
+    (
+        prog,
+        if errors.is_empty() {
+            0
+        } else {
+            PARSE_ERROR_CODE
+        },
+        errors,
+    )
+}
+#[cfg(test)]
+pub(crate) fn parse_with_code(s: &str) -> (parser::Program, u8) {
+    let (prog, code, _) = parse_with_code_and_errors(s);
+    (prog, code)
+}
+
+fn print_stmt(s: &Stmt) {
+    match s {
+        Stmt::Expression(e) => print_expr(e),
+        Stmt::Print(_, e) => print_expr(e),
+        Stmt::Block(..)
+        | Stmt::If(..)
+        | Stmt::While(..)
+        | Stmt::Break(..)
+        | Stmt::Continue(..)
+        | Stmt::Return(..) => println!("{}", s),
+        Stmt::Invalid => (),
+    }
+}
+// TODO Stmt should be a struct with expresion and type
+pub fn parse(s: &str, format: Format) -> ExitCode {
+    if format == Format::Json {
+        return print_json(parse_json(s));
+    }
+    let (expr, code, errors) = parse_with_code_and_errors(s);
+
+    for d in expr.declarations {
+        match d {
+            Decl::Statement(s) => print_stmt(&s),
+            Decl::Fun(f) => println!("{}", f),
+            Decl::Class(c) => println!("{}", c),
+            Decl::VarDecl(t, opt_e) => {
+                print!("[ token: [{}], expr: ", t);
+                match opt_e {
+                    Some(e) => print_expr(&e),
+                    None => print!("(only decl.)"),
+                }
+                print!("]");
+            }
+        }
+    }
+    errors.iter().for_each(|e| eprintln!("{}", e));
+
+    ExitCode::from(code)
+}
+
+fn print_expr(e: &parser::Expression) {
+    match e {
+        parser::Expression::Invalid => (),
+        ref valid => println!("{}", valid),
+    }
+}
+
+/// Evaluates program in s printing to stdout; errors are returned, not printed
+#[cfg(test)]
+pub(crate) fn evaluate_with_code(
+    s: &str,
+) -> (Vec<StatementEvalResult>, Option<crate::InterpreterError>, u8) {
+    let mut interpreter = Interpreter::with_writers(stdout(), std::io::sink());
+    match interpreter.run_source(s) {
+        Ok(results) => (results, None, interpreter.exit_status().unwrap_or(0)),
+        Err(e) => {
+            let code = e.exit_code();
+            (Vec::new(), Some(e), code)
+        }
+    }
+}
+
+pub fn evaluate(s: &str, format: Format) -> ExitCode {
+    if format == Format::Json {
+        return print_json(evaluate_json(s));
+    }
+    let mut interpreter = Interpreter::new();
+    match interpreter.run_source(s) {
+        Ok(result) => {
+            for r in result {
+                match r {
+                    StatementEvalResult::ExpressionStatementResult(er) => println!("{}", er),
+                    StatementEvalResult::BlockResult(vec) => vec.iter().for_each(|ser| {println!("{:?}", ser);}),
+                    // print statements were already written by evaluator
+                    _ => (),
+                }
+            }
+            ExitCode::from(interpreter.exit_status().unwrap_or(0))
+        }
+        Err(e) => ExitCode::from(e.exit_code()),
+    }
+}
+
+/// Runs program with script arguments args, writing to out and err;
+/// returns exit code as number (the one passed to `exit` if program called it)
+pub(crate) fn run_with_code<W: std::io::Write, E: std::io::Write>(
+    out: &mut W,
+    err: &mut E,
+    s: &str,
+    args: &[String],
+) -> u8 {
+    let mut interpreter = Interpreter::with_writers(out, err);
+    interpreter.set_args(args.to_vec());
+    match interpreter.run_source(s) {
+        Ok(_) => interpreter.exit_status().unwrap_or(0),
+        Err(e) => e.exit_code(),
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn runw<W: std::io::Write, E: std::io::Write>(out: &mut W, err: &mut E, s: &str) -> ExitCode {
+    ExitCode::from(run_with_code(out, err, s, &[]))
+}
+
+/// Runs program; args (script name first) are available to it through `argc()` and `argv(i)`
+pub fn run(s: &str, format: Format, args: &[String]) -> ExitCode {
+    if format == Format::Json {
+        return print_json(run_json(s, args));
+    }
+    ExitCode::from(run_with_code(&mut stdout(), &mut stderr(), s, args))
+}
+
+/// exit code of lint: warnings fail it only when denied
+fn lint_code(warnings: usize, deny_warnings: bool) -> u8 {
+    if deny_warnings && warnings > 0 {
+        PARSE_ERROR_CODE
+    } else {
+        0
+    }
+}
+
+pub(crate) fn lint_json(s: &str, deny_warnings: bool) -> (Json, u8) {
+    match linter::lint(s) {
+        Ok(warnings) => json_report(
+            [],
+            warnings.iter().map(json::warning).collect(),
+            lint_code(warnings.len(), deny_warnings),
+        ),
+        Err(e) => json_report([], json::interpreter_error(&e), e.exit_code()),
+    }
+}
+
+/// Writes warnings (or errors preventing the lint) with source snippets to err
+pub(crate) fn lintw<E: std::io::Write>(err: &mut E, s: &str, deny_warnings: bool) -> ExitCode {
+    match linter::lint(s) {
+        Ok(warnings) => {
+            for w in warnings.iter() {
+                let _ = writeln!(err, "{}", render(s, &w.to_string(), Some(&w.location), None));
+            }
+            ExitCode::from(lint_code(warnings.len(), deny_warnings))
+        }
+        Err(e) => {
+            let _ = writeln!(err, "{}", e.render(s));
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+/// Reports warnings about the program without running it
+pub fn lint(s: &str, format: Format, deny_warnings: bool) -> ExitCode {
+    if format == Format::Json {
+        return print_json(lint_json(s, deny_warnings));
+    }
+    lintw(&mut stderr(), s, deny_warnings)
+}
+
+/// Runs `// expect:` annotated test scripts found in dir (or given file,
+/// or standard input for `-`) and prints a summary
+pub fn test(dir: &str) -> ExitCode {
+    let res = if dir == "-" {
+        conformance::run_input(&mut stdout(), "<stdin>", contents(dir))
+    } else {
+        conformance::run_dir(&mut stdout(), std::path::Path::new(dir))
+    };
+    match res {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Cannot run tests in {}: {}", dir, e);
+            ExitCode::from(IO_ERROR_CODE)
+        }
+    }
+}
+
+/// Formats file in place (standard input `-` is written formatted to stdout);
+/// with check, only reports whether it is formatted (exit code 1 if it is not)
+pub fn fmt(filename: &str, check: bool) -> ExitCode {
+    let source = match contents(filename) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(e.exit_code());
+        }
+    };
+    let formatted = match crate::format_source(&source) {
+        Ok(f) => f,
+        Err(e) => {
+            eprintln!("{}", e.render(&source));
+            return ExitCode::from(e.exit_code());
+        }
+    };
+    if formatted != source && check {
+        eprintln!("{} is not formatted", filename);
+        return ExitCode::FAILURE;
+    }
+    if check {
+        return ExitCode::SUCCESS;
+    }
+    if filename == "-" {
+        print!("{}", formatted);
+        return ExitCode::SUCCESS;
+    }
+    if formatted == source {
+        return ExitCode::SUCCESS;
+    }
+    match std::fs::write(filename, formatted) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Cannot write {}: {}", filename, e);
+            ExitCode::from(IO_ERROR_CODE)
+        }
+    }
+}
+
+/// Runs program under debugger reading commands from stdin;
+/// args are passed to the program as by run
+pub fn debug(s: &str, args: &[String]) -> ExitCode {
+    let mut interpreter = Interpreter::new();
+    interpreter.set_args(args.to_vec());
+    interpreter.set_hook(Box::new(Debugger::new(s, std::io::stdin().lock(), stdout())));
+    match interpreter.run_source(s) {
+        Ok(_) => ExitCode::from(interpreter.exit_status().unwrap_or(0)),
+        Err(e) => ExitCode::from(e.exit_code()),
+    }
+}
+
+/// Reads Lox from stdin line by line, printing values of expression statements;
+/// program calling `exit` ends the session with its code
+pub fn repl() -> ExitCode {
+    match crate::repl::run(std::io::stdin().lock(), stdout(), stderr()) {
+        Ok(code) => ExitCode::from(code),
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
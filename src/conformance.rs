@@ -0,0 +1,192 @@
+/// Runner of Lox test scripts whose expected results are given by comments,
+/// as in the upstream Lox test suite:
+/// - `// expect: text` - line printed to stdout
+/// - `// expect runtime error: message` - runtime error reported at the comment's line
+/// - `// [line N] Error ...` or `// Error ...` - syntax or static error
+///   (at the comment's line in the latter form); `[java line N]` is accepted
+///   as `[line N]` and `[c line N]` errors are ignored. Lexeme may be quoted as
+///   upstream does: `Error at 'x':` matches `Error at x:` reported by this interpreter
+///
+/// Exit code is 65 if any syntax error is expected, 70 if a runtime error is,
+/// otherwise 0.
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cli::run_with_code,
+    interpreter::{PARSE_ERROR_CODE, RUNTIME_ERROR_CODE},
+    lexer::{self, LineNum},
+    token::TriviaKind,
+    utils::{contents, InputError},
+};
+
+/// Results a test script is expected to produce
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Expectations {
+    pub stdout: Vec<String>,
+    /// first lines of syntax and static error messages
+    pub errors: Vec<String>,
+    /// message and line
+    pub runtime_error: Option<(String, LineNum)>,
+}
+
+impl Expectations {
+    pub(crate) fn of(source: &str) -> Self {
+        let mut e = Expectations::default();
+        for t in lexer::trivia(source) {
+            if t.kind != TriviaKind::LineComment {
+                continue;
+            }
+            let text = t.text.trim_start();
+            if let Some(line) = text.strip_prefix("expect:") {
+                e.stdout.push(line.strip_prefix(' ').unwrap_or(line).to_string());
+            } else if let Some(message) = text.strip_prefix("expect runtime error:") {
+                e.runtime_error = Some((message.trim().to_string(), t.ln));
+            } else if text.starts_with("Error") {
+                e.errors.push(unquoted(format!("[line {}] {}", t.ln, text.trim_end())));
+            } else if let Some(error) = text.strip_prefix("[java line ") {
+                e.errors.push(unquoted(format!("[line {}", error.trim_end())));
+            } else if text.starts_with("[line ") {
+                e.errors.push(unquoted(text.trim_end().to_string()));
+            }
+        }
+        e
+    }
+
+    pub(crate) fn exit_code(&self) -> u8 {
+        if !self.errors.is_empty() {
+            PARSE_ERROR_CODE
+        } else if self.runtime_error.is_some() {
+            RUNTIME_ERROR_CODE
+        } else {
+            0
+        }
+    }
+}
+
+/// error message with upstream quotes around the lexeme it is at removed
+fn unquoted(error: String) -> String {
+    let Some(start) = error.find("Error at '").map(|i| i + "Error at ".len()) else {
+        return error;
+    };
+    match error[start + 1..].find("': ").map(|i| i + start + 1) {
+        Some(end) => format!("{}{}{}", &error[..start], &error[start + 1..end], &error[end + 1..]),
+        None => error,
+    }
+}
+
+/// lines of expected and actual text, prefixed with `-` and `+` where they differ
+fn diff(expected: &[String], actual: &[String]) -> String {
+    let mut lines = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => lines.push(format!("    {}", e)),
+            (e, a) => {
+                lines.extend(e.map(|e| format!("  - {}", e)));
+                lines.extend(a.map(|a| format!("  + {}", a)));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// Runs test script; returns description of each unmet expectation
+pub(crate) fn check(source: &str) -> Vec<String> {
+    let expected = Expectations::of(source);
+    let (mut out, mut err) = (Vec::new(), Vec::new());
+    let code = run_with_code(&mut out, &mut err, source, &[]);
+    let stdout = String::from_utf8_lossy(&out)
+        .lines()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    let stderr = String::from_utf8_lossy(&err).to_string();
+
+    let mut failures = Vec::new();
+    if stdout != expected.stdout {
+        failures.push(format!("stdout differs:\n{}", diff(&expected.stdout, &stdout)));
+    }
+    let errors = stderr
+        .lines()
+        .filter(|l| l.starts_with("[line "))
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    if errors != expected.errors {
+        failures.push(format!("errors differ:\n{}", diff(&expected.errors, &errors)));
+    }
+    if let Some((message, line)) = &expected.runtime_error {
+        let expected = [message.clone(), format!("[Line {}]", line)];
+        let actual = stderr.lines().take(2).map(str::to_string).collect::<Vec<_>>();
+        if actual != expected {
+            failures.push(format!("runtime error differs:\n{}", diff(&expected, &actual)));
+        }
+    }
+    if code != expected.exit_code() {
+        failures.push(format!(
+            "exit code: expected {}, got {}\n  stderr:\n{}",
+            expected.exit_code(),
+            code,
+            stderr.lines().map(|l| format!("    {}", l)).collect::<Vec<_>>().join("\n")
+        ));
+    }
+    failures
+}
+
+/// .lox files in dir and its subdirectories, sorted by path; a file is returned as is
+fn discover(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    if dir.is_file() {
+        return Ok(vec![dir.to_path_buf()]);
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(discover(&path)?);
+        } else if path.extension().is_some_and(|e| e == "lox") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Runs test script read from input named name, writing PASS/FAIL line with diffs
+/// of unmet expectations; unreadable script fails. Returns true if test passed
+pub(crate) fn run_input<W: Write>(
+    w: &mut W,
+    name: &str,
+    input: Result<String, InputError>,
+) -> io::Result<bool> {
+    let failures = match input {
+        Ok(source) => check(&source),
+        Err(e) => vec![e.to_string()],
+    };
+    if failures.is_empty() {
+        writeln!(w, "PASS {}", name)?;
+    } else {
+        writeln!(w, "FAIL {}", name)?;
+        for f in failures.iter() {
+            writeln!(w, "  {}", f)?;
+        }
+    }
+    Ok(failures.is_empty())
+}
+
+/// Runs all test scripts in dir (or the single script dir names), writing
+/// PASS/FAIL line for each, with diffs of failed ones, and a summary.
+/// Returns true if all tests passed
+pub(crate) fn run_dir<W: Write>(w: &mut W, dir: &Path) -> io::Result<bool> {
+    let (mut passed, mut failed) = (0, 0);
+    for path in discover(dir)? {
+        let name = path.display().to_string();
+        if run_input(w, &name, contents(&name))? {
+            passed += 1;
+        } else {
+            failed += 1;
+        }
+    }
+    writeln!(w, "{} passed, {} failed", passed, failed)?;
+    Ok(failed == 0)
+}
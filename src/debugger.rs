@@ -0,0 +1,182 @@
+/// Interactive step debugger: a Hook pausing the program before declarations and
+/// statements, where commands read from input set line breakpoints, step through
+/// the program and print variables visible in the current environment chain.
+///
+/// The program pauses before its first statement. End of input lets it run to
+/// completion without further pauses.
+use std::{
+    collections::BTreeSet,
+    io::{BufRead, Lines, Write},
+};
+
+use crate::{
+    diagnostics::RuntimeErrorKind,
+    environment::EnvRef,
+    evaluator::{Hook, Unwind},
+    lexer::LineNum,
+};
+
+const PROMPT: &str = "(debug) ";
+
+/// lines shown by `list` before and after current line
+const LIST_CONTEXT: LineNum = 2;
+
+const HELP: &str = "\
+break <line>    (b) pause before statements on line
+clear <line>        delete breakpoint
+step            (s) run to next statement, also into called functions
+next            (n) run to next statement in this function or its callers
+continue        (c) run to next breakpoint
+print <name>    (p) print value of variable
+list            (l) show source around current line
+quit            (q) stop the program
+help            (h) show this help
+empty line repeats last command";
+
+/// When the program pauses next
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// before next statement
+    Step,
+    /// before next statement with call depth at most the given one
+    Next(usize),
+    /// at breakpoints only
+    Continue,
+}
+
+pub(crate) struct Debugger<R, W> {
+    source: Vec<String>,
+    commands: Lines<R>,
+    out: W,
+    breakpoints: BTreeSet<LineNum>,
+    mode: Mode,
+    /// line of statement before which hook was last called
+    last_line: Option<LineNum>,
+    last_command: String,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    /// debugger of program in source reading commands from input
+    pub(crate) fn new(source: &str, input: R, out: W) -> Self {
+        Debugger {
+            source: source.lines().map(str::to_string).collect(),
+            commands: input.lines(),
+            out,
+            breakpoints: BTreeSet::new(),
+            mode: Mode::Step,
+            last_line: None,
+            last_command: String::new(),
+        }
+    }
+
+    /// source line with its number, marked if it is the current one
+    fn source_line(&self, line: LineNum, current: LineNum) -> String {
+        let marker = if line == current { ">" } else { " " };
+        let text = line
+            .checked_sub(1)
+            .and_then(|i| self.source.get(i as usize))
+            .map_or("", |s| s.as_str());
+        format!("{} {} | {}", marker, line, text)
+    }
+
+    fn line_arg(&self, arg: Option<&str>) -> Result<LineNum, String> {
+        let line = arg
+            .and_then(|a| a.parse::<LineNum>().ok())
+            .ok_or("Expect line number.")?;
+        if line == 0 || line as usize > self.source.len() {
+            return Err(format!("No line {} in program.", line));
+        }
+        Ok(line)
+    }
+
+    /// runs commands until one resumes the program (Ok) or stops it (Err)
+    fn pause(&mut self, line: LineNum, depth: usize, env: &EnvRef) -> Result<(), Unwind> {
+        let _ = writeln!(self.out, "{}", self.source_line(line, line));
+        loop {
+            let _ = write!(self.out, "{}", PROMPT);
+            let _ = self.out.flush();
+            let input = match self.commands.next() {
+                Some(Ok(input)) => input,
+                _ => {
+                    let _ = writeln!(self.out);
+                    self.breakpoints.clear();
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+            };
+            let input = match input.trim() {
+                "" => self.last_command.clone(),
+                command => command.to_string(),
+            };
+            self.last_command = input.clone();
+            let mut words = input.split_whitespace();
+            let command = words.next().unwrap_or_default();
+            let arg = words.next();
+            let message = match command {
+                "step" | "s" => {
+                    self.mode = Mode::Step;
+                    return Ok(());
+                }
+                "next" | "n" => {
+                    self.mode = Mode::Next(depth);
+                    return Ok(());
+                }
+                "continue" | "c" => {
+                    self.mode = Mode::Continue;
+                    return Ok(());
+                }
+                "quit" | "q" => return Err(Unwind::Exit(0)),
+                "break" | "b" => match self.line_arg(arg) {
+                    Ok(l) => {
+                        self.breakpoints.insert(l);
+                        format!("Breakpoint at line {}.", l)
+                    }
+                    Err(e) => e,
+                },
+                "clear" => match self.line_arg(arg) {
+                    Ok(l) if self.breakpoints.remove(&l) => {
+                        format!("Deleted breakpoint at line {}.", l)
+                    }
+                    Ok(l) => format!("No breakpoint at line {}.", l),
+                    Err(e) => e,
+                },
+                "print" | "p" => match arg {
+                    Some(name) => match env.borrow().get_var(name) {
+                        Some(value) => format!("{} = {}", name, value),
+                        None => RuntimeErrorKind::UndefinedVariable(name.to_string()).to_string(),
+                    },
+                    None => "Expect variable name.".to_string(),
+                },
+                "list" | "l" => {
+                    let first = line.saturating_sub(LIST_CONTEXT).max(1);
+                    let last = (line + LIST_CONTEXT).min(self.source.len() as LineNum);
+                    (first..=last)
+                        .map(|l| self.source_line(l, line))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+                "help" | "h" => HELP.to_string(),
+                other => format!("Unknown command: {} (try help)", other),
+            };
+            let _ = writeln!(self.out, "{}", message);
+        }
+    }
+}
+
+impl<R: BufRead, W: Write> Hook for Debugger<R, W> {
+    fn before(&mut self, line: LineNum, depth: usize, env: &EnvRef) -> Result<(), Unwind> {
+        // breakpoint pauses once when its line is entered, not before each statement on it
+        let entered = self.last_line != Some(line);
+        self.last_line = Some(line);
+        let paused = match self.mode {
+            Mode::Step => true,
+            Mode::Next(d) => depth <= d,
+            Mode::Continue => false,
+        } || (entered && self.breakpoints.contains(&line));
+        if paused {
+            self.pause(line, depth, env)
+        } else {
+            Ok(())
+        }
+    }
+}
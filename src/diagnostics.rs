@@ -0,0 +1,496 @@
+/// Error types of all interpreter phases and rendering of the offending
+/// source line with the located range underlined.
+///
+/// Every error kind has a stable code: E1xx for lexing, E2xx for parsing
+/// and static analysis, E3xx for runtime errors; lint warnings have Wxxx codes.
+use std::{error::Error, fmt::Display};
+
+use crate::{
+    lexer::LineNum,
+    token::{LexicalError, Span, Token, TokenType},
+};
+
+/// Where an error occurred in source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: LineNum,
+    pub span: Span,
+}
+
+impl Location {
+    pub(crate) fn of(t: &Token) -> Self {
+        Location {
+            line: t.ln,
+            span: t.span,
+        }
+    }
+}
+
+impl LexicalError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnknownToken(_) => "E101",
+            Self::UnterminatedString => "E102",
+            Self::InvalidNumber => "E103",
+            Self::UnterminatedBlockComment => "E104",
+        }
+    }
+}
+
+/// Invalid token found by the lexer
+#[derive(Debug, Clone, PartialEq)]
+pub struct LexError {
+    pub kind: LexicalError,
+    pub location: Location,
+    pub note: Option<String>,
+}
+
+impl LexError {
+    /// error described by token of type TokenType::Unknown
+    pub(crate) fn from_token(t: &Token) -> Option<Self> {
+        match &t.typ {
+            TokenType::Unknown(kind) => Some(LexError {
+                kind: kind.clone(),
+                location: Location::of(t),
+                note: match kind {
+                    LexicalError::UnterminatedString => {
+                        Some("string literal is never closed with '\"'".to_string())
+                    }
+                    _ => None,
+                },
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl Display for LexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "[line {}] Error: {}", self.location.line, self.kind)
+    }
+}
+
+/// Kinds of errors found by parser and by resolver (static analysis)
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseErrorKind {
+    /// missing token or construct, described as e.g. "';' after expression"
+    Expect(String),
+    ExpectExpression,
+    TooManyParameters,
+    TooManyArguments,
+    InvalidAssignmentTarget,
+    TopLevelReturn,
+    /// break or continue (keyword) outside of a loop
+    LoopControlOutsideLoop(String),
+    UndefinedLabel(String),
+    /// expressions or statements nested deeper than the parser allows
+    TooMuchNesting,
+    // found by resolver
+    ReadInOwnInitializer,
+    AlreadyDeclared,
+    InitializerReturnsValue,
+    ThisOutsideClass,
+    SuperOutsideClass,
+    SuperWithoutSuperclass,
+    InheritsFromItself,
+}
+
+impl ParseErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Expect(_) => "E201",
+            Self::ExpectExpression => "E202",
+            Self::TooManyParameters => "E203",
+            Self::TooManyArguments => "E204",
+            Self::InvalidAssignmentTarget => "E205",
+            Self::TopLevelReturn => "E206",
+            Self::LoopControlOutsideLoop(_) => "E207",
+            Self::UndefinedLabel(_) => "E208",
+            Self::TooMuchNesting => "E209",
+            Self::ReadInOwnInitializer => "E251",
+            Self::AlreadyDeclared => "E252",
+            Self::InitializerReturnsValue => "E253",
+            Self::ThisOutsideClass => "E254",
+            Self::SuperOutsideClass => "E255",
+            Self::SuperWithoutSuperclass => "E256",
+            Self::InheritsFromItself => "E257",
+        }
+    }
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Expect(what) => write!(f, "Expect {}.", what),
+            Self::ExpectExpression => f.write_str("Expect expression."),
+            Self::TooManyParameters => f.write_str("Can't have more than 255 parameters."),
+            Self::TooManyArguments => f.write_str("Can't have more than 255 arguments."),
+            Self::InvalidAssignmentTarget => f.write_str("Invalid assignment target."),
+            Self::TopLevelReturn => f.write_str("Can't return from top-level code."),
+            Self::LoopControlOutsideLoop(k) => write!(f, "Can't use '{}' outside of a loop.", k),
+            Self::UndefinedLabel(l) => write!(f, "No enclosing loop labeled '{}'.", l),
+            Self::TooMuchNesting => f.write_str("Too much nesting."),
+            Self::ReadInOwnInitializer => {
+                f.write_str("Can't read local variable in its own initializer.")
+            }
+            Self::AlreadyDeclared => {
+                f.write_str("Already a variable with this name in this scope.")
+            }
+            Self::InitializerReturnsValue => {
+                f.write_str("Can't return a value from an initializer.")
+            }
+            Self::ThisOutsideClass => f.write_str("Can't use 'this' outside of a class."),
+            Self::SuperOutsideClass => f.write_str("Can't use 'super' outside of a class."),
+            Self::SuperWithoutSuperclass => {
+                f.write_str("Can't use 'super' in a class with no superclass.")
+            }
+            Self::InheritsFromItself => f.write_str("A class can't inherit from itself."),
+        }
+    }
+}
+
+/// Error found by parser or resolver at a token
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub location: Location,
+    /// lexeme of the offending token; None at end of input
+    pub lexeme: Option<String>,
+    pub note: Option<String>,
+}
+
+impl ParseError {
+    pub(crate) fn at(t: &Token, kind: ParseErrorKind) -> Self {
+        ParseError {
+            kind,
+            location: Location::of(t),
+            lexeme: match t.typ {
+                TokenType::Eof => None,
+                _ => Some(t.s.clone()),
+            },
+            note: None,
+        }
+    }
+
+    pub(crate) fn with_note(self, note: &str) -> Self {
+        ParseError {
+            note: Some(note.to_string()),
+            ..self
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.lexeme {
+            Some(l) => write!(f, "[line {}] Error at {}: {}", self.location.line, l, self.kind),
+            None => write!(f, "[line {}] Error at end: {}", self.location.line, self.kind),
+        }
+    }
+}
+
+/// Kinds of errors raised while evaluating a program
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuntimeErrorKind {
+    OperandMustBeNumber,
+    OperandsMustBeNumbers,
+    OperandsMustBeNumbersOrStrings,
+    UndefinedVariable(String),
+    UndefinedProperty(String),
+    NotCallable,
+    ArityMismatch { expected: usize, got: usize },
+    OnlyInstancesHaveProperties,
+    OnlyInstancesHaveFields,
+    SuperclassMustBeClass,
+    /// operator (given as written) not defined for operand types
+    UnsupportedOperator(String),
+    /// error reported by a native function
+    Native(String),
+    /// program with syntax errors was given to the evaluator
+    InvalidProgram,
+    /// function calls nested deeper than the evaluator allows
+    StackOverflow,
+}
+
+impl RuntimeErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::OperandMustBeNumber => "E301",
+            Self::OperandsMustBeNumbers => "E302",
+            Self::OperandsMustBeNumbersOrStrings => "E303",
+            Self::UndefinedVariable(_) => "E304",
+            Self::UndefinedProperty(_) => "E305",
+            Self::NotCallable => "E306",
+            Self::ArityMismatch { .. } => "E307",
+            Self::OnlyInstancesHaveProperties => "E308",
+            Self::OnlyInstancesHaveFields => "E309",
+            Self::SuperclassMustBeClass => "E310",
+            Self::UnsupportedOperator(_) => "E311",
+            Self::Native(_) => "E312",
+            Self::InvalidProgram => "E313",
+            Self::StackOverflow => "E314",
+        }
+    }
+}
+
+impl Display for RuntimeErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::OperandMustBeNumber => f.write_str("Operand must be a number."),
+            Self::OperandsMustBeNumbers => f.write_str("Operands must be numbers."),
+            Self::OperandsMustBeNumbersOrStrings => {
+                f.write_str("Operands must be two numbers or two strings.")
+            }
+            Self::UndefinedVariable(name) => write!(f, "Undefined variable '{}'.", name),
+            Self::UndefinedProperty(name) => write!(f, "Undefined property '{}'.", name),
+            Self::NotCallable => f.write_str("Can only call functions and classes."),
+            Self::ArityMismatch { expected, got } => {
+                write!(f, "Expected {} arguments but got {}.", expected, got)
+            }
+            Self::OnlyInstancesHaveProperties => f.write_str("Only instances have properties."),
+            Self::OnlyInstancesHaveFields => f.write_str("Only instances have fields."),
+            Self::SuperclassMustBeClass => f.write_str("Superclass must be a class."),
+            Self::UnsupportedOperator(op) => {
+                write!(f, "Operator '{}' is not supported for this operand.", op)
+            }
+            Self::Native(msg) => f.write_str(msg),
+            Self::InvalidProgram => f.write_str("Can't evaluate program with syntax errors."),
+            Self::StackOverflow => f.write_str("Stack overflow."),
+        }
+    }
+}
+
+/// Error raised while evaluating a program
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalError {
+    pub kind: RuntimeErrorKind,
+    /// None when error is not related to source, e.g. raised by native function
+    pub location: Option<Location>,
+    pub note: Option<String>,
+}
+
+impl EvalError {
+    pub(crate) fn new(kind: RuntimeErrorKind) -> Self {
+        EvalError {
+            kind,
+            location: None,
+            note: None,
+        }
+    }
+
+    /// error located at token t
+    pub(crate) fn at(kind: RuntimeErrorKind, t: &Token) -> Self {
+        EvalError {
+            location: Some(Location::of(t)),
+            ..Self::new(kind)
+        }
+    }
+
+    /// error to be returned by native functions
+    pub fn native(message: &str) -> Self {
+        Self::new(RuntimeErrorKind::Native(message.to_string()))
+    }
+
+    pub(crate) fn with_note(self, note: String) -> Self {
+        EvalError {
+            note: Some(note),
+            ..self
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl Error for EvalError {}
+impl Display for EvalError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.location {
+            Some(l) => write!(f, "{}\n[Line {}]", self.kind, l.line),
+            None => write!(f, "{}", self.kind),
+        }
+    }
+}
+
+/// Kinds of warnings reported by the linter
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarningKind {
+    UnusedVariable(String),
+    ShadowedVariable(String),
+    NoEffect,
+    /// assignment target (variable or property) assigned to itself
+    SelfAssignment(String),
+    /// `==` or `!=` of literals of different types (given by names)
+    /// and the constant result of the comparison
+    LiteralTypeMismatch { left: String, right: String, result: bool },
+}
+
+impl WarningKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable(_) => "W001",
+            Self::ShadowedVariable(_) => "W002",
+            Self::NoEffect => "W003",
+            Self::SelfAssignment(_) => "W004",
+            Self::LiteralTypeMismatch { .. } => "W005",
+        }
+    }
+
+    /// name accepted in suppression directives besides the code
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UnusedVariable(_) => "unused-variable",
+            Self::ShadowedVariable(_) => "shadowed-variable",
+            Self::NoEffect => "no-effect",
+            Self::SelfAssignment(_) => "self-assignment",
+            Self::LiteralTypeMismatch { .. } => "literal-type-mismatch",
+        }
+    }
+}
+
+impl Display for WarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnusedVariable(v) => write!(f, "Local variable '{}' is never used.", v),
+            Self::ShadowedVariable(v) => {
+                write!(f, "Variable '{}' shadows a variable declared in an enclosing scope.", v)
+            }
+            Self::NoEffect => f.write_str("Expression statement has no effect."),
+            Self::SelfAssignment(target) => write!(f, "'{}' is assigned to itself.", target),
+            Self::LiteralTypeMismatch { left, right, result } => {
+                write!(f, "Comparison of {} with {} is always {}.", left, right, result)
+            }
+        }
+    }
+}
+
+/// Suspicious code found by the linter
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub location: Location,
+}
+
+impl Warning {
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[line {}] Warning {}: {}",
+            self.location.line,
+            self.code(),
+            self.kind
+        )
+    }
+}
+
+/// Message followed by the source line the error points at and optional note
+pub(crate) fn render(
+    source: &str,
+    message: &str,
+    location: Option<&Location>,
+    note: Option<&String>,
+) -> String {
+    let mut s = message.to_string();
+    if let Some(l) = location.filter(|l| l.span.is_known()) {
+        s.push('\n');
+        s.push_str(&snippet(source, l.span));
+    }
+    if let Some(n) = note {
+        s.push_str("\n  = note: ");
+        s.push_str(n);
+    }
+    s
+}
+
+/// Source line containing span start followed by carets under the span, e.g.
+/// ```text
+///    3 | var a = b + ;
+///      |             ^
+/// ```
+/// Span continuing on next lines is underlined to the end of the first line.
+pub(crate) fn snippet(source: &str, span: Span) -> String {
+    let start = span.start.min(source.len());
+    let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = source[start..].find('\n').map_or(source.len(), |i| start + i);
+    let line = source[line_start..line_end].trim_end_matches('\r');
+    let ln = source[..line_start].matches('\n').count() + 1;
+
+    let end = span.end.clamp(start, line_end);
+    let pad = source[line_start..start].chars().count();
+    let width = source[start..end].chars().count().max(1);
+    let gutter = " ".repeat(ln.to_string().len());
+    format!(
+        "{} | {}\n{} | {}{}",
+        ln,
+        line,
+        gutter,
+        " ".repeat(pad),
+        "^".repeat(width)
+    )
+}
+
+#[cfg(test)]
+mod diagnostics_test {
+    use super::{snippet, ParseError, ParseErrorKind};
+    use crate::token::{Span, Token, TokenType};
+
+    #[test]
+    fn underlines_span_in_its_line() {
+        let src = "var a = 1;\nprint a + ;\n";
+        let span = Span {
+            start: 21,
+            end: 22,
+            col: 11,
+        };
+        assert_eq!(snippet(src, span), "2 | print a + ;\n  |           ^");
+    }
+
+    #[test]
+    fn underlines_empty_span_at_end_with_one_caret() {
+        let src = "print 1";
+        let span = Span {
+            start: 7,
+            end: 7,
+            col: 8,
+        };
+        assert_eq!(snippet(src, span), "1 | print 1\n  |        ^");
+    }
+
+    #[test]
+    fn underlines_multiline_span_to_end_of_line() {
+        let src = "print \"ab\ncd\";";
+        let span = Span {
+            start: 6,
+            end: 13,
+            col: 7,
+        };
+        assert_eq!(snippet(src, span), "1 | print \"ab\n  |       ^^^");
+    }
+
+    #[test]
+    fn parse_error_points_at_token_or_end() {
+        let semicolon = Token::new(TokenType::Semicolon, 2, ";".into());
+        let e = ParseError::at(&semicolon, ParseErrorKind::ExpectExpression);
+        assert_eq!(e.to_string(), "[line 2] Error at ;: Expect expression.");
+        assert_eq!(e.code(), "E202");
+
+        let eof = Token::new(TokenType::Eof, 3, "".into());
+        let e = ParseError::at(&eof, ParseErrorKind::Expect("'}' after block".into()));
+        assert_eq!(e.to_string(), "[line 3] Error at end: Expect '}' after block.");
+    }
+}
@@ -1,15 +1,20 @@
-use core::borrow;
 /// Represents environment - variables and their values in Lox program
-/// This is part of evaluator
-use std::{borrow::Borrow, cell::RefCell, collections::HashMap, hash::Hash, rc::Rc};
+/// This is part of evaluator.
+/// Environments form a chain of scopes shared by reference: a block or a function call
+/// creates a new environment pointing to its enclosing one, and closures keep
+/// the environment they were declared in alive.
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
-    evaluator::{EvalError, EvalResult},
-    tests,
+    diagnostics::{EvalError, RuntimeErrorKind},
+    evaluator::EvalResult,
 };
-#[derive(Clone)]
+
+/// Shared, mutable reference to environment
+pub(crate) type EnvRef = Rc<RefCell<Environment>>;
+
 pub(crate) struct Environment {
-    enclosig: Option<Box<Environment>>,
+    enclosig: Option<EnvRef>,
     values: HashMap<String, EvalResult>,
 }
 
@@ -21,13 +26,18 @@ impl Environment {
         }
     }
 
-    pub(crate) fn new_with_enclosing(env: Environment) -> Self {
+    pub(crate) fn new_with_enclosing(env: EnvRef) -> Self {
         Environment {
-            enclosig: Some(Box::new(env)),
+            enclosig: Some(env),
             values: HashMap::new(),
         }
     }
 
+    /// wraps environment in shared reference
+    pub(crate) fn into_ref(self) -> EnvRef {
+        Rc::new(RefCell::new(self))
+    }
+
     pub(crate) fn define(&mut self, name: String, value: EvalResult) -> EvalResult {
         self.values.insert(name, value.clone());
         value
@@ -38,12 +48,40 @@ impl Environment {
             Some(v.clone())
         } else {
             match &self.enclosig {
-                Some(e) => e.get_var(s),
+                Some(e) => e.borrow().get_var(s),
                 None => None
             }
         }
     }
 
+    /// value of variable defined in environment distance scopes up the chain
+    pub(crate) fn get_at(&self, distance: usize, s: &str) -> Option<EvalResult> {
+        if distance == 0 {
+            self.values.get(s).cloned()
+        } else {
+            self.enclosig.as_ref()?.borrow().get_at(distance - 1, s)
+        }
+    }
+
+    /// assigns value to variable defined in environment distance scopes up the chain
+    pub(crate) fn assign_at(
+        &mut self,
+        distance: usize,
+        t: &crate::token::Token,
+        er: EvalResult,
+    ) -> std::result::Result<EvalResult, EvalError> {
+        if distance == 0 {
+            self.values.insert(t.s.clone(), er.clone());
+            Ok(er)
+        } else {
+            match self.enclosig {
+                Some(ref ev) => ev.borrow_mut().assign_at(distance - 1, t, er),
+                None => Err(EvalError::at(RuntimeErrorKind::UndefinedVariable(t.s.clone()), t)),
+            }
+        }
+    }
+
+    /// assigns value to variable in the nearest scope defining it
     pub(crate) fn assign(
         &mut self,
         t: &crate::token::Token,
@@ -51,10 +89,8 @@ impl Environment {
     ) -> std::result::Result<EvalResult, EvalError> {
         if !self.values.contains_key(&t.s) {
             match self.enclosig {
-                Some(ref mut ev) => ev.assign(&t, er),
-                None => Err(EvalError {
-                    s: format!("Undefined variable '{}'", &t.s),
-                })
+                Some(ref ev) => ev.borrow_mut().assign(t, er),
+                None => Err(EvalError::at(RuntimeErrorKind::UndefinedVariable(t.s.clone()), t))
             }
         } else {
             self.values.insert(t.s.clone(), er.clone());
@@ -87,4 +123,38 @@ mod environment_test {
             None => panic!("Environment should get value for fii")
         }
    }
+
+   #[test]
+   fn assign_in_nested_persists_in_enclosing() {
+        let outer = Environment::new().into_ref();
+        outer.borrow_mut().define("a".to_string(), EvalResult::Nil);
+
+        let mut inner = Environment::new_with_enclosing(outer.clone());
+        let assigned = inner.assign(&Token::of_string("a", 1), EvalResult::String { value: "set".to_string(), token: Token::of_string("set", 1)});
+        assert!(assigned.is_ok(), "a should be assignable from nested environment");
+        drop(inner);
+
+        let a = outer.borrow().get_var("a");
+        match a {
+            Some(EvalResult::String { value, token: _ }) => assert_eq!(value, "set"),
+            other => panic!("a should be set in enclosing environment, was {:?}", other),
+        }
+   }
+
+   #[test]
+   fn get_at_skips_shadowing_scopes() {
+        let outer = Environment::new().into_ref();
+        outer.borrow_mut().define("a".to_string(), EvalResult::Nil);
+        let mut inner = Environment::new_with_enclosing(outer);
+        inner.define("a".to_string(), EvalResult::String { value: "inner".to_string(), token: Token::of_string("inner", 1)});
+
+        assert_eq!(inner.get_at(1, "a"), Some(EvalResult::Nil));
+        assert_eq!(inner.get_at(0, "b"), None);
+   }
+
+   #[test]
+   fn assign_undefined_fails() {
+        let mut env = Environment::new_with_enclosing(Environment::new().into_ref());
+        assert!(env.assign(&Token::of_string("nope", 1), EvalResult::Nil).is_err());
+   }
 }
@@ -1,20 +1,63 @@
-use std::{borrow::BorrowMut, cell::RefCell, error::Error, fmt::Display, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, fmt::Display, io::Write, rc::Rc};
 
 use crate::{
-    environment::Environment,
-    parser::{Binary, Decl, Expression, Program, Stmt, Unary},
+    diagnostics::{EvalError, Location, RuntimeErrorKind},
+    environment::{EnvRef, Environment},
+    lexer::LineNum,
+    parser::{Binary, Class, Decl, Expression, Function, Logical, Program, Stmt, Unary},
     token::{Numeric, Token, TokenType},
 };
 
-pub type Result = std::result::Result<EvalResult, EvalError>;
-pub type StatementResult = std::result::Result<StatementEvalResult, EvalError>;
+pub type Result = std::result::Result<EvalResult, Unwind>;
+pub type StatementResult = std::result::Result<StatementEvalResult, Unwind>;
+
+/// Reason evaluation stops before the end of program
+#[derive(Debug, Clone, PartialEq)]
+pub enum Unwind {
+    Error(EvalError),
+    /// not an error: `exit(code)` was called and program stops with this exit status
+    Exit(u8),
+}
+
+impl Unwind {
+    /// error changed by f; exit is passed on unchanged
+    fn map_error(self, f: impl FnOnce(EvalError) -> EvalError) -> Self {
+        match self {
+            Self::Error(e) => Self::Error(f(e)),
+            exit => exit,
+        }
+    }
+}
+
+impl From<EvalError> for Unwind {
+    fn from(e: EvalError) -> Self {
+        Self::Error(e)
+    }
+}
 
 /// Results of Stmt evaluation
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, PartialEq)]
 pub enum StatementEvalResult {
     ExpressionStatementResult(EvalResult),
     PrintStatementResult(EvalResult),
-    BlockResult(Vec<StatementEvalResult>)
+    BlockResult(Vec<StatementEvalResult>),
+    /// control flow signal: leave loop with given label (or innermost loop)
+    BreakResult(Option<String>),
+    /// control flow signal: skip to next iteration of loop with given label (or innermost loop)
+    ContinueResult(Option<String>),
+    /// control flow signal: return value from the function being called
+    ReturnResult(EvalResult),
+}
+
+impl StatementEvalResult {
+    /// true for break, continue and return signals which unwind enclosing blocks
+    fn is_control_flow(&self) -> bool {
+        matches!(
+            self,
+            Self::BreakResult(_) | Self::ContinueResult(_) | Self::ReturnResult(_)
+        )
+    }
 }
 /// Result of expression evaluation
 #[derive(Debug, PartialEq)]
@@ -23,9 +66,256 @@ pub enum EvalResult {
     Boolean { value: bool, token: Token },
     String { value: String, token: Token },
     Reserved { value: String, token: Token },
+    Function(Rc<LoxFunction>),
+    Class(Rc<LoxClass>),
+    Instance(Rc<LoxInstance>),
+    Native(Rc<dyn NativeFunction>),
     Nil,
 }
 
+/// Function implemented in Rust and callable from Lox programs.
+/// Natives are defined in globals by Evaluator::new
+pub trait NativeFunction {
+    /// name of global variable the function is bound to
+    fn name(&self) -> &str;
+    /// number of arguments; checked before call like for Lox functions
+    fn arity(&self) -> usize;
+    fn call(&self, args: &[EvalResult]) -> Result;
+}
+
+impl std::fmt::Debug for dyn NativeFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "NativeFunction({})", self.name())
+    }
+}
+
+/// natives are equal only to themselves
+impl PartialEq for dyn NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+}
+
+/// `clock()` - seconds elapsed since the Unix epoch
+struct Clock;
+
+impl NativeFunction for Clock {
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _args: &[EvalResult]) -> Result {
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0.0, |d| d.as_secs_f64());
+        let token = Token::new(TokenType::Number(Numeric(secs)), 0, secs.to_string());
+        Ok(EvalResult::of_numeric(secs, &token))
+    }
+}
+
+/// value of number argument that is an integer in range 0..=max
+fn index_arg(arg: &EvalResult, max: usize) -> Option<usize> {
+    match arg {
+        EvalResult::Numeric { value, .. }
+            if value.fract() == 0.0 && (0.0..=max as f64).contains(value) =>
+        {
+            Some(*value as usize)
+        }
+        _ => None,
+    }
+}
+
+/// `exit(code)` - stops the program with exit status code (0 to 255)
+struct Exit;
+
+impl NativeFunction for Exit {
+    fn name(&self) -> &str {
+        "exit"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, args: &[EvalResult]) -> Result {
+        match index_arg(&args[0], u8::MAX as usize) {
+            Some(code) => Err(Unwind::Exit(code as u8)),
+            None => Err(EvalError::native("Exit code must be an integer from 0 to 255.").into()),
+        }
+    }
+}
+
+/// `argc()` - number of script arguments, including script name
+pub(crate) struct Argc(pub(crate) Rc<Vec<String>>);
+
+impl NativeFunction for Argc {
+    fn name(&self) -> &str {
+        "argc"
+    }
+
+    fn arity(&self) -> usize {
+        0
+    }
+
+    fn call(&self, _args: &[EvalResult]) -> Result {
+        let n = self.0.len() as f64;
+        let token = Token::new(TokenType::Number(Numeric(n)), 0, n.to_string());
+        Ok(EvalResult::of_numeric(n, &token))
+    }
+}
+
+/// `argv(i)` - i-th script argument as string; argument 0 is script name
+pub(crate) struct Argv(pub(crate) Rc<Vec<String>>);
+
+impl NativeFunction for Argv {
+    fn name(&self) -> &str {
+        "argv"
+    }
+
+    fn arity(&self) -> usize {
+        1
+    }
+
+    fn call(&self, args: &[EvalResult]) -> Result {
+        let arg = index_arg(&args[0], usize::MAX).and_then(|i| self.0.get(i));
+        match arg {
+            Some(a) => {
+                let token = Token::new(TokenType::StringLiteral, 0, a.clone());
+                Ok(EvalResult::of_string(a.clone(), &token))
+            }
+            None => Err(EvalError::native(&format!(
+                "Argument index must be an integer less than {}.",
+                self.0.len()
+            ))
+            .into()),
+        }
+    }
+}
+
+/// natives available to every program; there are no script arguments
+/// until Interpreter::set_args is called
+fn natives() -> Vec<Rc<dyn NativeFunction>> {
+    let args = Rc::new(Vec::new());
+    vec![
+        Rc::new(Clock),
+        Rc::new(Exit),
+        Rc::new(Argc(args.clone())),
+        Rc::new(Argv(args)),
+    ]
+}
+
+/// Function declared in Lox program together with environment it was declared in
+pub struct LoxFunction {
+    declaration: Rc<Function>,
+    closure: EnvRef,
+    /// initializers always return `this`
+    is_initializer: bool,
+}
+
+/// closure is not printed - it may contain the function itself
+impl std::fmt::Debug for LoxFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LoxFunction({})", self.declaration.name.s)
+    }
+}
+
+impl LoxFunction {
+    fn arity(&self) -> usize {
+        self.declaration.params.len()
+    }
+
+    /// method bound to instance: `this` is defined in a scope
+    /// between the closure and the call environment
+    fn bind(&self, instance: Rc<LoxInstance>) -> LoxFunction {
+        let mut env = Environment::new_with_enclosing(self.closure.clone());
+        env.define("this".to_string(), EvalResult::Instance(instance));
+        LoxFunction {
+            declaration: self.declaration.clone(),
+            closure: env.into_ref(),
+            is_initializer: self.is_initializer,
+        }
+    }
+}
+
+/// functions are equal only to themselves
+impl PartialEq for LoxFunction {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Class declared in Lox program; calling it creates an instance
+#[derive(Debug)]
+pub struct LoxClass {
+    name: Token,
+    superclass: Option<Rc<LoxClass>>,
+    methods: HashMap<String, Rc<LoxFunction>>,
+}
+
+impl LoxClass {
+    /// method declared in this class or inherited from superclass chain
+    fn find_method(&self, name: &str) -> Option<Rc<LoxFunction>> {
+        self.methods
+            .get(name)
+            .cloned()
+            .or_else(|| self.superclass.as_ref().and_then(|sc| sc.find_method(name)))
+    }
+
+    /// number of arguments of initializer (0 if class has no initializer)
+    fn arity(&self) -> usize {
+        self.find_method("init").map_or(0, |init| init.arity())
+    }
+}
+
+/// classes are equal only to themselves
+impl PartialEq for LoxClass {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// Instance of a class with its fields
+pub struct LoxInstance {
+    class: Rc<LoxClass>,
+    fields: RefCell<HashMap<String, EvalResult>>,
+}
+
+impl LoxInstance {
+    /// field with given name or method bound to this instance
+    fn get(instance: &Rc<LoxInstance>, name: &Token) -> Result {
+        if let Some(v) = instance.fields.borrow().get(&name.s) {
+            return Ok(v.clone());
+        }
+        match instance.class.find_method(&name.s) {
+            Some(m) => Ok(EvalResult::Function(Rc::new(m.bind(instance.clone())))),
+            None => runtime_error(RuntimeErrorKind::UndefinedProperty(name.s.clone()), name),
+        }
+    }
+
+    fn set(&self, name: &Token, value: EvalResult) -> EvalResult {
+        self.fields.borrow_mut().insert(name.s.clone(), value.clone());
+        value
+    }
+}
+
+/// fields are not printed - they may contain the instance itself
+impl std::fmt::Debug for LoxInstance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LoxInstance({})", self.class.name.s)
+    }
+}
+
+/// instances are equal only to themselves
+impl PartialEq for LoxInstance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
 impl EvalResult {
     fn of_boolean(value: bool, token: &Token) -> EvalResult {
         Self::Boolean {
@@ -54,6 +344,52 @@ impl EvalResult {
             token: token.clone(),
         }
     }
+
+    fn is_nil(&self) -> bool {
+        match self {
+            Self::Nil => true,
+            Self::Reserved { value, token: _ } => value == "nil",
+            _ => false,
+        }
+    }
+
+    /// name of value's type used in error notes
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            Self::Numeric { .. } => "number",
+            Self::Boolean { .. } => "boolean",
+            Self::String { .. } => "string",
+            Self::Reserved { .. } | Self::Nil => "nil",
+            Self::Function(_) => "function",
+            Self::Class(_) => "class",
+            Self::Instance(_) => "instance",
+            Self::Native(_) => "native function",
+        }
+    }
+
+    /// token the value originates from (none for implicit nil)
+    fn token(&self) -> Option<&Token> {
+        match self {
+            Self::Numeric { value: _, token }
+            | Self::Boolean { value: _, token }
+            | Self::String { value: _, token }
+            | Self::Reserved { value: _, token } => Some(token),
+            Self::Function(f) => Some(&f.declaration.name),
+            Self::Class(c) => Some(&c.name),
+            Self::Instance(i) => Some(&i.class.name),
+            Self::Native(_) | Self::Nil => None,
+        }
+    }
+
+    /// Lox truthiness: only nil and false are falsey
+    pub(crate) fn is_truthy(&self) -> bool {
+        match self {
+            Self::Nil => false,
+            Self::Boolean { value, token: _ } => *value,
+            Self::Reserved { value, token: _ } => value != "nil",
+            _ => true,
+        }
+    }
 }
 impl Clone for EvalResult {
     fn clone(&self) -> Self {
@@ -74,6 +410,10 @@ impl Clone for EvalResult {
                 value: value.clone(),
                 token: token.clone(),
             },
+            Self::Function(f) => Self::Function(f.clone()),
+            Self::Class(c) => Self::Class(c.clone()),
+            Self::Instance(i) => Self::Instance(i.clone()),
+            Self::Native(n) => Self::Native(n.clone()),
             Self::Nil => Self::Nil,
         }
     }
@@ -85,30 +425,16 @@ impl Display for EvalResult {
             Self::Boolean { value: b, token: _ } => b.to_string(),
             Self::String { value: s, token: _ } => s.to_string(),
             Self::Reserved { value: s, token: _ } => s.to_string(),
+            Self::Function(fun) => format!("<fn {}>", fun.declaration.name.s),
+            Self::Class(c) => c.name.s.clone(),
+            Self::Instance(i) => format!("{} instance", i.class.name.s),
+            Self::Native(_) => "<native fn>".to_string(),
             Self::Nil => "nil".to_string(),
         };
         f.write_str(&s)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-pub struct EvalError {
-    pub s: String,
-}
-
-impl EvalError {
-    fn new(s: String) -> EvalError {
-        EvalError { s }
-    }
-}
-
-impl Error for EvalError {}
-impl Display for EvalError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&self.s)
-    }
-}
-
 // /// Creates Err variant from statuc string
 // fn err(s: &'static str) -> Result {
 //     Err(EvalError { s: s.into() })
@@ -119,172 +445,554 @@ impl Display for EvalError {
 //     Ok(EvalResult::Numeric { field1: n , ltok))
 // }
 
+/// deepest nesting of Lox function calls; deeper recursion is a runtime error
+/// instead of overflowing the native stack
+const MAX_CALL_DEPTH: usize = 1000;
+
+/// native stack left when a call is evaluated on a newly allocated stack segment;
+/// covers the deepest frames of one call, so the caller's stack size does not matter
+const STACK_RED_ZONE: usize = 256 * 1024;
+
+/// size of native stack segments allocated for deep recursion
+const STACK_SEGMENT: usize = 4 * 1024 * 1024;
+
+/// Observer called by the evaluator before each declaration and statement
+/// (blocks aside), e.g. a debugger pausing the program
+pub(crate) trait Hook {
+    /// depth is the number of function calls in progress and env the innermost
+    /// environment; error (or exit) stops evaluation as if the statement failed
+    fn before(
+        &mut self,
+        line: LineNum,
+        depth: usize,
+        env: &EnvRef,
+    ) -> std::result::Result<(), Unwind>;
+}
+
 /// Evaluator of expressions
-pub struct Evaluator {
-    env: Environment,
+/// Output of print statements is written to `out`
+pub struct Evaluator<'a> {
+    env: EnvRef,
+    /// outermost environment; unresolved variables are looked up here
+    globals: EnvRef,
+    out: Box<dyn Write + 'a>,
+    hook: Option<Box<dyn Hook + 'a>>,
+    /// number of function calls in progress
+    depth: usize,
+}
+
+impl Default for Evaluator<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl Evaluator {
+impl<'a> Evaluator<'a> {
     pub fn new() -> Self {
-        Evaluator {
-            env: Environment::new(),
-        }
+        Self::with_writer(Box::new(std::io::stdout()))
+    }
+
+    /// Creates evaluator printing to boxed writer; globals are populated with natives
+    pub(crate) fn with_writer(out: Box<dyn Write + 'a>) -> Self {
+        let globals = Environment::new().into_ref();
+        let mut ev = Evaluator {
+            env: globals.clone(),
+            globals,
+            out,
+            hook: None,
+            depth: 0,
+        };
+        natives().into_iter().for_each(|n| ev.define_native(n));
+        ev
+    }
+
+    /// Defines global variable named after native function
+    pub fn define_native(&mut self, native: Rc<dyn NativeFunction>) {
+        let name = native.name().to_string();
+        self.define_global(&name, EvalResult::Native(native));
+    }
+
+    pub(crate) fn define_global(&mut self, name: &str, value: EvalResult) {
+        self.globals.borrow_mut().define(name.to_string(), value);
+    }
+
+    pub(crate) fn get_global(&self, name: &str) -> Option<EvalResult> {
+        self.globals.borrow().get_var(name)
+    }
+
+    pub(crate) fn set_hook(&mut self, hook: Box<dyn Hook + 'a>) {
+        self.hook = Some(hook);
     }
 
-    fn eval_primary(&self, token: Token) -> Result {
-        let t = &token;
-        match token.typ {
+    /// calls hook (if any) before declaration or statement starting at line
+    fn before(&mut self, line: Option<LineNum>) -> std::result::Result<(), Unwind> {
+        let Some(line) = line else {
+            return Ok(());
+        };
+        let Some(mut hook) = self.hook.take() else {
+            return Ok(());
+        };
+        let res = hook.before(line, self.depth, &self.env);
+        self.hook = Some(hook);
+        res
+    }
+
+    fn eval_primary(&self, t: &Token) -> Result {
+        match t.typ {
             TokenType::True => Ok(EvalResult::of_boolean(true, t)),
             TokenType::False => Ok(EvalResult::of_boolean(false, t)),
             TokenType::Nil => Ok(EvalResult::of_reserved("nil", t)),
             TokenType::StringLiteral => Ok(EvalResult::of_string(t.s.clone(), t)),
             TokenType::Number(Numeric(f)) => Ok(EvalResult::of_numeric(f, t)),
-            _ => Err(EvalError::new("unimplemented!".into())),
-        }
-    }
-
-    fn eval_unary(&mut self, unary: Unary, ex: Expression) -> Result {
-        let res = self.eval_expr(ex);
-        match res {
-            Ok(val) => match val {
-                EvalResult::Nil => Ok(EvalResult::Nil),
-                EvalResult::Numeric {
-                    value: n,
-                    token: ref tok,
-                } => match unary {
-                    Unary::Minus => Ok(EvalResult::of_numeric(-n, tok)),
-                    Unary::Not => Ok(EvalResult::of_boolean(n == 0.0, tok)),
-                    _ => {
-                        runtime_error("Numeric arg can only be used with <minus> operator", tok.ln)
-                    }
-                },
-                EvalResult::Reserved {
-                    value: word,
-                    token: ref tok,
-                } => match unary {
-                    Unary::Not => match word.to_lowercase().as_str() {
-                        "nil" => Ok(EvalResult::of_boolean(true, tok)),
-                        _ => runtime_error("Reselved word has no unary oper", tok.ln),
-                    },
-                    Unary::Minus => runtime_error("Operand must be a number.", tok.ln),
-                    _ => runtime_error("Unary operator not supported", tok.ln),
-                },
-                EvalResult::Boolean {
-                    value: v,
-                    token: ref tok,
-                } => match unary {
-                    Unary::Not => Ok(EvalResult::of_boolean(!v, tok)),
-                    Unary::Minus => runtime_error("Operand must be a number.", tok.ln),
-                    _ => runtime_error("Bool arg can only be used with negation", tok.ln),
-                },
-                EvalResult::String {
-                    value: _s,
-                    token: tok,
-                } => match unary {
-                    Unary::Minus => runtime_error("Operand must be a number.", tok.ln),
-                    _op => runtime_error("Operator cannot be used on string", tok.ln),
-                },
+            _ => runtime_error(RuntimeErrorKind::InvalidProgram, t),
+        }
+    }
+
+    /// `!` negates truthiness of any value, `-` needs a number
+    fn eval_unary(&mut self, unary: &Unary, ex: &Expression) -> Result {
+        let val = self.eval_expr(ex)?;
+        let tok = val
+            .token()
+            .cloned()
+            .unwrap_or_else(|| Token::new(TokenType::Nil, 0, "nil".into()));
+        match (unary, &val) {
+            (Unary::Not, _) => Ok(EvalResult::of_boolean(!val.is_truthy(), &tok)),
+            (Unary::Minus, EvalResult::Numeric { value, .. }) => {
+                Ok(EvalResult::of_numeric(-value, &tok))
+            }
+            (Unary::Minus, _) => runtime_error(RuntimeErrorKind::OperandMustBeNumber, &tok),
+            (Unary::Invalid(_), _) => unsupported_unary(unary, &tok),
+        }
+    }
+
+    /// operator errors are located at the whole binary expression
+    /// and note the types of operands
+    /// t is the operator's token
+    fn eval_binary(
+        &mut self,
+        lex: &Expression,
+        op: &Binary,
+        t: &Token,
+        rex: &Expression,
+    ) -> Result {
+        let location = lex.span().zip(rex.span()).zip(lex.line()).map(|((l, r), line)| Location {
+            line,
+            span: l.to(r),
+        });
+        let lr = self.eval_expr(lex)?;
+        let rr = self.eval_expr(rex)?;
+        let note = format!(
+            "left operand is {}, right operand is {}",
+            lr.type_name(),
+            rr.type_name()
+        );
+
+        calculate(lr, op.clone(), t, rr).map_err(|u| {
+            u.map_error(|e| EvalError {
+                location: location.or(e.location),
+                ..e.with_note(note)
+            })
+        })
+    }
+
+    /// returns the operand deciding the result;
+    /// right operand is not evaluated when left one decides
+    fn eval_logical(&mut self, lex: &Expression, op: &Logical, rex: &Expression) -> Result {
+        let lr = self.eval_expr(lex)?;
+        match op {
+            Logical::Or if lr.is_truthy() => Ok(lr),
+            Logical::And if !lr.is_truthy() => Ok(lr),
+            _ => self.eval_expr(rex),
+        }
+    }
+
+    fn eval_call(&mut self, callee: &Expression, paren: &Token, args: &[Expression]) -> Result {
+        let callee = self.eval_expr(callee)?;
+        let mut arg_values = Vec::new();
+        for a in args {
+            arg_values.push(self.eval_expr(a)?);
+        }
+        let arity = match &callee {
+            EvalResult::Function(f) => f.arity(),
+            EvalResult::Class(c) => c.arity(),
+            EvalResult::Native(n) => n.arity(),
+            other => {
+                let note = format!("called value is {}", other.type_name());
+                let e = EvalError::at(RuntimeErrorKind::NotCallable, paren).with_note(note);
+                return Err(e.into());
+            }
+        };
+        if arg_values.len() != arity {
+            let kind = RuntimeErrorKind::ArityMismatch {
+                expected: arity,
+                got: arg_values.len(),
+            };
+            return runtime_error(kind, paren);
+        }
+        if self.depth >= MAX_CALL_DEPTH && !matches!(callee, EvalResult::Native(_)) {
+            return runtime_error(RuntimeErrorKind::StackOverflow, paren);
+        }
+        match callee {
+            EvalResult::Function(f) => stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+                self.call_function(&f, arg_values)
+            }),
+            EvalResult::Class(c) => stacker::maybe_grow(STACK_RED_ZONE, STACK_SEGMENT, || {
+                self.instantiate(c, arg_values)
+            }),
+            // errors of natives are located at the call
+            EvalResult::Native(n) => n.call(&arg_values).map_err(|u| {
+                u.map_error(|e| EvalError {
+                    location: e.location.or(Some(Location::of(paren))),
+                    ..e
+                })
+            }),
+            _ => unreachable!("arity is known only for callables"),
+        }
+    }
+
+    /// methods of a subclass close over environment defining `super`
+    fn eval_class(&mut self, c: &Class) -> Result {
+        let superclass = match &c.superclass {
+            Some(sc) => match self.eval_expr(sc)? {
+                EvalResult::Class(class) => Some(class),
+                _ => {
+                    let at = match sc {
+                        Expression::Variable(t, _) => t,
+                        _ => &c.name,
+                    };
+                    return runtime_error(RuntimeErrorKind::SuperclassMustBeClass, at);
+                }
             },
-            Err(_) => res,
+            None => None,
+        };
+        let closure = match &superclass {
+            Some(class) => {
+                let mut env = Environment::new_with_enclosing(self.env.clone());
+                env.define("super".to_string(), EvalResult::Class(class.clone()));
+                env.into_ref()
+            }
+            None => self.env.clone(),
+        };
+        let methods = c
+            .methods
+            .iter()
+            .map(|m| {
+                let method = LoxFunction {
+                    declaration: m.clone(),
+                    closure: closure.clone(),
+                    is_initializer: m.name.s == "init",
+                };
+                (m.name.s.clone(), Rc::new(method))
+            })
+            .collect();
+        let class = EvalResult::Class(Rc::new(LoxClass {
+            name: c.name.clone(),
+            superclass,
+            methods,
+        }));
+        Ok(self.env.borrow_mut().define(c.name.s.clone(), class))
+    }
+
+    /// looks method up starting from superclass and binds it to `this`,
+    /// which is defined one scope closer than `super`
+    fn eval_super(&mut self, method: &Token, depth: Option<usize>) -> Result {
+        let d = depth.unwrap_or(0);
+        let superclass = self.env.borrow().get_at(d, "super");
+        let object = self.env.borrow().get_at(d.saturating_sub(1), "this");
+        match (superclass, object) {
+            (Some(EvalResult::Class(sc)), Some(EvalResult::Instance(i))) => {
+                match sc.find_method(&method.s) {
+                    Some(m) => Ok(EvalResult::Function(Rc::new(m.bind(i)))),
+                    None => {
+                        runtime_error(RuntimeErrorKind::UndefinedProperty(method.s.clone()), method)
+                    }
+                }
+            }
+            _ => runtime_error(RuntimeErrorKind::UndefinedVariable("super".into()), method),
         }
     }
 
-    fn eval_binary(&mut self, lex: Expression, op: Binary, rex: Expression) -> Result {
-        let lr = self.eval_expr(lex);
-        let rr = self.eval_expr(rex);
+    /// creates instance of class and runs its initializer
+    fn instantiate(&mut self, class: Rc<LoxClass>, args: Vec<EvalResult>) -> Result {
+        let instance = Rc::new(LoxInstance {
+            class: class.clone(),
+            fields: RefCell::new(HashMap::new()),
+        });
+        if let Some(init) = class.find_method("init") {
+            self.call_function(&init.bind(instance.clone()), args)?;
+        }
+        Ok(EvalResult::Instance(instance))
+    }
 
-        calculate(lr?, op, rr?)
+    fn eval_get(&mut self, object: &Expression, name: &Token) -> Result {
+        match self.eval_expr(object)? {
+            EvalResult::Instance(i) => LoxInstance::get(&i, name),
+            _ => runtime_error(RuntimeErrorKind::OnlyInstancesHaveProperties, name),
+        }
     }
 
+    fn eval_set(&mut self, object: &Expression, name: &Token, value: &Expression) -> Result {
+        match self.eval_expr(object)? {
+            EvalResult::Instance(i) => {
+                let value = self.eval_expr(value)?;
+                Ok(i.set(name, value))
+            }
+            _ => runtime_error(RuntimeErrorKind::OnlyInstancesHaveFields, name),
+        }
+    }
+
+    /// evaluates function body in new environment (enclosed by function's closure)
+    /// with parameters bound to args
+    fn call_function(&mut self, f: &LoxFunction, args: Vec<EvalResult>) -> Result {
+        let mut call_env = Environment::new_with_enclosing(f.closure.clone());
+        for (param, arg) in f.declaration.params.iter().zip(args) {
+            call_env.define(param.s.clone(), arg);
+        }
+        let oldenv = std::mem::replace(&mut self.env, call_env.into_ref());
+        self.depth += 1;
+        let mut res = Ok(EvalResult::Nil);
+        for d in f.declaration.body.iter() {
+            match self.eval_decl(d) {
+                Ok(StatementEvalResult::ReturnResult(value)) => {
+                    res = Ok(value);
+                    break;
+                }
+                Ok(_) => (),
+                Err(e) => {
+                    res = Err(e);
+                    break;
+                }
+            }
+        }
+        self.env = oldenv;
+        self.depth -= 1;
+        if f.is_initializer && res.is_ok() {
+            return Ok(f.closure.borrow().get_at(0, "this").unwrap_or(EvalResult::Nil));
+        }
+        res
+    }
+
+    /// evaluates declarations of the program until first error
     pub(crate) fn eval(&mut self, p: Program) -> Vec<StatementResult> {
-        p.declarations.iter().map(|d| self.eval_decl(d)).collect()
+        let mut results = Vec::new();
+        for d in p.declarations.iter() {
+            // errors without location point at the whole declaration
+            let r = self.eval_decl(d).map_err(|u| {
+                u.map_error(|e| EvalError {
+                    location: e
+                        .location
+                        .or_else(|| Some(Location { line: d.line()?, span: d.span()? })),
+                    ..e
+                })
+            });
+            let is_err = r.is_err();
+            results.push(r);
+            if is_err {
+                break;
+            }
+        }
+        results
     }
 
-    fn eval_stmt(&mut self, s: Stmt) -> StatementResult {
+    fn eval_stmt(&mut self, s: &Stmt) -> StatementResult {
+        self.before(s.line())?;
         match s {
-            Stmt::Print(e) => self
-                .eval_expr(e)
-                .map(StatementEvalResult::PrintStatementResult),
+            Stmt::Print(_, e) => {
+                let value = self.eval_expr(e)?;
+                let _ = writeln!(self.out, "{}", value);
+                Ok(StatementEvalResult::PrintStatementResult(value))
+            }
             Stmt::Expression(e) => self
                 .eval_expr(e)
                 .map(StatementEvalResult::ExpressionStatementResult),
             Stmt::Block(v) => self.eval_block(v),
-            Stmt::Invalid(s) => Err(EvalError { s })
+            Stmt::If(_, c, t, e) => self.eval_if(c, t, e.as_deref()),
+            Stmt::While(_, c, b, l) => self.eval_while(c, b, l.as_ref()),
+            Stmt::Break(_, l) => Ok(StatementEvalResult::BreakResult(label(l))),
+            Stmt::Continue(_, l) => Ok(StatementEvalResult::ContinueResult(label(l))),
+            Stmt::Return(_, e) => {
+                let value = match e {
+                    Some(e) => self.eval_expr(e)?,
+                    None => EvalResult::Nil,
+                };
+                Ok(StatementEvalResult::ReturnResult(value))
+            }
+            Stmt::Invalid => Err(EvalError::new(RuntimeErrorKind::InvalidProgram).into()),
         }
     }
 
-    pub fn eval_expr(&mut self, e: Expression) -> Result {
+    pub fn eval_expr(&mut self, e: &Expression) -> Result {
         match e {
             Expression::Primary(t) => self.eval_primary(t),
-            Expression::Paren(e) => self.eval_expr(*e),
-            Expression::UnaryEx(unary, ex) => self.eval_unary(unary, *ex),
-            Expression::BinaryEx(l, op, r) => self.eval_binary(*l, op, *r),
-            Expression::Variable(t) => self.eval_variable(&t.s),
-            Expression::Assign(t, e) =>self.eval_assign(&t, e),
-            Expression::Invalid(s) => Err(EvalError::new(format!("Invalid expresstion: {}", s))),
+            Expression::Paren(_, e, _) => self.eval_expr(e),
+            Expression::UnaryEx(unary, _, ex) => self.eval_unary(unary, ex),
+            Expression::BinaryEx(l, op, t, r) => self.eval_binary(l, op, t, r),
+            Expression::LogicalEx(l, op, _, r) => self.eval_logical(l, op, r),
+            Expression::Call(c, paren, args) => self.eval_call(c, paren, args),
+            Expression::Get(o, name) => self.eval_get(o, name),
+            Expression::Set(o, name, v) => self.eval_set(o, name, v),
+            Expression::This(t, depth) => self.eval_variable(t, *depth),
+            Expression::Super(_, method, depth) => self.eval_super(method, *depth),
+            Expression::Variable(t, depth) => self.eval_variable(t, *depth),
+            Expression::Assign(t, e, depth) => self.eval_assign(t, e, *depth),
+            Expression::Invalid => Err(EvalError::new(RuntimeErrorKind::InvalidProgram).into()),
         }
     }
 
-    fn eval_variable(&self, s: &str) -> std::result::Result<EvalResult, EvalError> {
-        self.env
-            .get_var(s)
-            .ok_or(EvalError::new(format!("Undefined variable '{}'.", s)))
+    /// looks variable up depth scopes above current one or in globals if depth is None
+    fn eval_variable(&self, t: &Token, depth: Option<usize>) -> Result {
+        let value = match depth {
+            Some(d) => self.env.borrow().get_at(d, &t.s),
+            None => self.globals.borrow().get_var(&t.s),
+        };
+        let kind = RuntimeErrorKind::UndefinedVariable(t.s.clone());
+        value.ok_or_else(|| EvalError::at(kind, t).into())
     }
 
-    fn eval_decl(
-        &mut self,
-        d: &crate::parser::Decl,
-    ) -> std::result::Result<StatementEvalResult, EvalError> {
+    fn eval_decl(&mut self, d: &crate::parser::Decl) -> StatementResult {
+        // statements call the hook themselves
+        if !matches!(d, Decl::Statement(_)) {
+            self.before(d.line())?;
+        }
         match d {
-            Decl::Statement(s) => self.eval_stmt(s.clone()),
+            Decl::Statement(s) => self.eval_stmt(s),
+            Decl::Fun(f) => {
+                let fun = EvalResult::Function(Rc::new(LoxFunction {
+                    declaration: f.clone(),
+                    closure: self.env.clone(),
+                    is_initializer: false,
+                }));
+                Ok(StatementEvalResult::ExpressionStatementResult(
+                    self.env.borrow_mut().define(f.name.s.clone(), fun),
+                ))
+            }
+            Decl::Class(c) => Ok(StatementEvalResult::ExpressionStatementResult(
+                self.eval_class(c)?,
+            )),
             Decl::VarDecl(t, opt_e) => {
                 let value = match opt_e {
                     None => Ok(EvalResult::Nil),
-                    Some(e) => self.eval_expr(e.clone()),
+                    Some(e) => self.eval_expr(e),
                 };
                 value.map(|eval_res| {
                     StatementEvalResult::ExpressionStatementResult(
-                        self.env.define(t.s.clone(), eval_res),
+                        self.env.borrow_mut().define(t.s.clone(), eval_res),
                     )
                 })
             }
         }
     }
 
-    fn eval_assign(&mut self, t: &Token, e: Box<Expression>) -> std::result::Result<EvalResult, EvalError> {
-        match self.eval_expr(*e) {
-            Ok(er) => self.env.assign(t, er),
+    fn eval_assign(
+        &mut self,
+        t: &Token,
+        e: &Expression,
+        depth: Option<usize>,
+    ) -> Result {
+        match self.eval_expr(e) {
+            Ok(er) => Ok(match depth {
+                Some(d) => self.env.borrow_mut().assign_at(d, t, er)?,
+                None => self.globals.borrow_mut().assign(t, er)?,
+            }),
             Err(e) => Err(e),
         }
     }
 
-    /// should add new enf atthis call
-    fn eval_block(&mut self, v: Vec<Decl> ) -> StatementResult {
-        let oldenv = self.env.clone();
-        self.env = Environment::new_with_enclosing(oldenv.clone());
+    /// evaluates branch selected by condition; when no branch is taken
+    /// result is an empty block
+    fn eval_if(&mut self, c: &Expression, t: &Stmt, e: Option<&Stmt>) -> StatementResult {
+        if self.eval_expr(c)?.is_truthy() {
+            self.eval_stmt(t)
+        } else if let Some(e) = e {
+            self.eval_stmt(e)
+        } else {
+            Ok(StatementEvalResult::BlockResult(Vec::new()))
+        }
+    }
+
+    /// evaluates body as long as condition is truthy.
+    /// Break and continue signals without label or with this loop's label
+    /// are consumed here, signals for outer loops end this loop and are passed up.
+    fn eval_while(&mut self, c: &Expression, b: &Stmt, label: Option<&Token>) -> StatementResult {
+        let label = label.map(|l| l.s.as_str());
+        let targets_this = |l: &Option<String>| l.is_none() || l.as_deref() == label;
+        while self.eval_expr(c)?.is_truthy() {
+            match self.eval_stmt(b)? {
+                StatementEvalResult::BreakResult(l) if targets_this(&l) => break,
+                StatementEvalResult::ContinueResult(l) if targets_this(&l) => (),
+                signal if signal.is_control_flow() => return Ok(signal),
+                _ => (),
+            }
+            // hook sees each return to the condition
+            self.before(c.line())?;
+        }
+        Ok(StatementEvalResult::BlockResult(Vec::new()))
+    }
+
+    /// evaluates declarations in new environment nested in current one
+    fn eval_block(&mut self, v: &[Decl]) -> StatementResult {
+        let block_env = Environment::new_with_enclosing(self.env.clone()).into_ref();
+        let oldenv = std::mem::replace(&mut self.env, block_env);
         let mut v_eval = Vec::new();
-        let mut err: Option<EvalError> = None;
+        let mut res: Option<StatementResult> = None;
         for s in v {
-            match  self.eval_decl(&s) {
+            match  self.eval_decl(s) {
+                Ok(sv) if sv.is_control_flow() => { res = Some(Ok(sv)); break; },
                 Ok(sv) => { v_eval.push(sv); },
-                Err(e) => { err = Some(e); break; } // should I break?
+                Err(e) => { res = Some(Err(e)); break; } // should I break?
             }
         };
 
 
         self.env = oldenv;
-        let res = err.map_or_else(||  Ok(StatementEvalResult::BlockResult(v_eval)), |eval_error| Err(eval_error));
-        res
+        res.unwrap_or(Ok(StatementEvalResult::BlockResult(v_eval)))
     }
 }
 
-fn runtime_error(arg: &str, ln: u64) -> Result {
-    Err(EvalError {
-        s: format!("{}\n[Line {}]", arg, ln),
-    })
+/// name of optional loop label carried by break or continue signal
+fn label(l: &Option<Token>) -> Option<String> {
+    l.as_ref().map(|l| l.s.clone())
 }
 
-fn calculate(lv: EvalResult, op: Binary, rv: EvalResult) -> Result {
+fn runtime_error(kind: RuntimeErrorKind, t: &Token) -> Result {
+    Err(EvalError::at(kind, t).into())
+}
+
+fn unsupported_unary(op: &Unary, t: &Token) -> Result {
+    runtime_error(RuntimeErrorKind::UnsupportedOperator(op.to_string()), t)
+}
+
+/// operands of op have types op is not defined for
+fn operands_error(op: &Binary, t: &Token) -> Result {
+    match op {
+        Binary::Plus => runtime_error(RuntimeErrorKind::OperandsMustBeNumbersOrStrings, t),
+        Binary::Invalid(_) => {
+            runtime_error(RuntimeErrorKind::UnsupportedOperator(op.to_string()), t)
+        }
+        _ => runtime_error(RuntimeErrorKind::OperandsMustBeNumbers, t),
+    }
+}
+
+/// nil is only equal to nil; other operators are not defined for nil
+fn calculate_nil(lv: EvalResult, op: Binary, t: &Token, rv: EvalResult) -> Result {
+    let both_nil = lv.is_nil() && rv.is_nil();
+    match op {
+        Binary::EqualEqual => Ok(EvalResult::of_boolean(both_nil, t)),
+        Binary::NotEqual => Ok(EvalResult::of_boolean(!both_nil, t)),
+        _ => operands_error(&op, t),
+    }
+}
+
+/// value of binary operation; t is the operator's token
+fn calculate(lv: EvalResult, op: Binary, t: &Token, rv: EvalResult) -> Result {
+    if lv.is_nil() || rv.is_nil() {
+        return calculate_nil(lv, op, t, rv);
+    }
+    // values of different types are never equal
+    let same_type = std::mem::discriminant(&lv) == std::mem::discriminant(&rv);
+    if let (false, Binary::EqualEqual | Binary::NotEqual) = (same_type, &op) {
+        return Ok(EvalResult::of_boolean(op == Binary::NotEqual, t));
+    }
     match lv {
         EvalResult::Numeric {
             value: l,
@@ -304,20 +1012,13 @@ fn calculate(lv: EvalResult, op: Binary, rv: EvalResult) -> Result {
                 Binary::GreaterEqual => Ok(EvalResult::of_boolean(l >= r, ltok)),
                 Binary::EqualEqual => Ok(EvalResult::of_boolean(l == r, ltok)),
                 Binary::NotEqual => Ok(EvalResult::of_boolean(l != r, ltok)),
-                Binary::Invalid(_) => runtime_error("Invalid binary operator", ltok.ln),
+                Binary::Invalid(_) => operands_error(&op, ltok),
             },
             EvalResult::String {
                 value: ref _s,
                 token: ref _rtok,
-            } => match op {
-                Binary::EqualEqual => Ok(EvalResult::of_boolean(false, ltok)),
-                Binary::NotEqual => Ok(EvalResult::of_boolean(false, ltok)),
-                Binary::Plus => {
-                    runtime_error("Operands must be two numbers or two strings.", ltok.ln)
-                }
-                _ => runtime_error("Only num != str and num == str supported", ltok.ln),
-            },
-            _ => runtime_error("Operands must be numbers.", ltok.ln),
+            } => operands_error(&op, ltok),
+            _ => operands_error(&op, ltok),
         },
         EvalResult::String {
             value: ref l,
@@ -330,7 +1031,7 @@ fn calculate(lv: EvalResult, op: Binary, rv: EvalResult) -> Result {
                 Binary::Plus => Ok(EvalResult::of_string(l.to_owned() + r, ltok)),
                 Binary::EqualEqual => Ok(EvalResult::of_boolean(l == r, ltok)),
                 Binary::NotEqual => Ok(EvalResult::of_boolean(l != r, ltok)),
-                _ => runtime_error("Only plus allowed on strings", ltok.ln),
+                _ => operands_error(&op, ltok),
             },
             EvalResult::Numeric {
                 value: n,
@@ -338,11 +1039,11 @@ fn calculate(lv: EvalResult, op: Binary, rv: EvalResult) -> Result {
             } => match op {
                 Binary::Multiply => Ok(EvalResult::of_string(l.repeat(n.round() as usize), ltok)),
                 Binary::Plus => {
-                    runtime_error("Operands must be two numbers or two strings.", ltok.ln)
+                    operands_error(&op, ltok)
                 }
-                _ => runtime_error("Only str*num and str+str allowed", ltok.ln),
+                _ => operands_error(&op, ltok),
             },
-            _ => runtime_error("No other binary operations on strings", ltok.ln),
+            _ => operands_error(&op, ltok),
         },
         EvalResult::Boolean {
             value: lv,
@@ -360,23 +1061,31 @@ fn calculate(lv: EvalResult, op: Binary, rv: EvalResult) -> Result {
                     value: lv != rv,
                     token: ltok,
                 }),
-                _ => runtime_error("Bool operators allowed: only == and !=.", ltok.ln),
-            },
-            _ => match op {
-                Binary::EqualEqual => Ok(EvalResult::Boolean {
-                    value: false,
-                    token: ltok,
-                }),
-                Binary::NotEqual => Ok(EvalResult::Boolean {
-                    value: true,
-                    token: ltok,
-                }),
-                Binary::Multiply => runtime_error("Operands must be numbers.", ltok.ln),
-                _ => runtime_error("Operator not supported", ltok.ln),
+                _ => operands_error(&op, &ltok),
             },
+            _ => operands_error(&op, &ltok),
         },
+        EvalResult::Function(_)
+        | EvalResult::Class(_)
+        | EvalResult::Instance(_)
+        | EvalResult::Native(_) => calculate_identity(lv, op, t, rv),
+        _ => operands_error(&op, t),
+    }
+}
 
-        _ => runtime_error("Expected numeric arg", 1),
+/// functions, classes, instances and natives are only equal to themselves
+fn calculate_identity(lv: EvalResult, op: Binary, t: &Token, rv: EvalResult) -> Result {
+    let same = match (&lv, &rv) {
+        (EvalResult::Function(l), EvalResult::Function(r)) => Rc::ptr_eq(l, r),
+        (EvalResult::Class(l), EvalResult::Class(r)) => Rc::ptr_eq(l, r),
+        (EvalResult::Instance(l), EvalResult::Instance(r)) => Rc::ptr_eq(l, r),
+        (EvalResult::Native(l), EvalResult::Native(r)) => Rc::ptr_eq(l, r),
+        _ => false,
+    };
+    match op {
+        Binary::EqualEqual => Ok(EvalResult::of_boolean(same, t)),
+        Binary::NotEqual => Ok(EvalResult::of_boolean(!same, t)),
+        _ => operands_error(&op, t),
     }
 }
 
@@ -399,6 +1108,7 @@ mod test_evaluator {
                 typ: tt.clone(),
                 ln,
                 s: tt.to_string(),
+                span: Default::default(),
             }
         }
 
@@ -407,6 +1117,7 @@ mod test_evaluator {
                 typ: TokenType::StringLiteral,
                 ln,
                 s: s.to_string(),
+                span: Default::default(),
             }
         }
 
@@ -415,6 +1126,7 @@ mod test_evaluator {
                 typ: TokenType::Number(n.clone()),
                 ln,
                 s: n.to_string(),
+                span: Default::default(),
             }
         }
         pub(crate) fn nil(ln: LineNum) -> Token {
@@ -422,6 +1134,7 @@ mod test_evaluator {
                 typ: TokenType::Nil,
                 ln,
                 s: "nil".to_string(),
+                span: Default::default(),
             }
         }
     }
@@ -439,7 +1152,7 @@ mod test_evaluator {
     fn eval_nil() {
         let expr = crate::parser::Expression::Primary(Token::nil(1));
         let mut e = Evaluator::new();
-        if let Ok(EvalResult::Reserved { value, token }) = e.eval_expr(expr) {
+        if let Ok(EvalResult::Reserved { value, token }) = e.eval_expr(&expr) {
             assert_eq!(value, "nil");
             assert_eq!(token.typ, TokenType::Nil)
         }
@@ -448,14 +1161,14 @@ mod test_evaluator {
     fn eval_string() {
         let expr = crate::parser::Expression::Primary(Token::of_string("hello", 1));
         let mut e = Evaluator::new();
-        match e.eval_expr(expr) {
+        match e.eval_expr(&expr) {
             Ok(EvalResult::String { value, token }) => {
                 assert_eq!(value, "hello");
                 assert_eq!(token.typ, TokenType::StringLiteral);
             }
             Ok(other_than_str) => panic!("{} should evauate ot string result", other_than_str),
             Err(error) => {
-                panic!("EvalError: {}", error)
+                panic!("EvalError: {:?}", error)
             }
         }
     }
@@ -464,7 +1177,7 @@ mod test_evaluator {
     fn eval_number() {
         let expr = crate::parser::Expression::Primary(Token::of_numeric(Numeric(12f64), 1));
         let mut e = Evaluator::new();
-        match e.eval_expr(expr) {
+        match e.eval_expr(&expr) {
             Ok(EvalResult::Numeric { value, token }) => {
                 assert_eq!(value, 12f64);
                 assert_eq!(token.typ, TokenType::Number(token::Numeric(12f64)))
@@ -478,18 +1191,84 @@ mod test_evaluator {
         }
     }
 
+    struct Twice;
+
+    impl super::NativeFunction for Twice {
+        fn name(&self) -> &str {
+            "twice"
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn call(&self, args: &[EvalResult]) -> super::Result {
+            match &args[0] {
+                EvalResult::Numeric { value, token } => Ok(EvalResult::of_numeric(value * 2.0, token)),
+                other => panic!("twice called with {}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn eval_native_call() {
+        use crate::parser::Expression;
+        let mut e = Evaluator::new();
+        e.define_native(std::rc::Rc::new(Twice));
+        let call = |args: Vec<Expression>| {
+            Expression::Call(
+                Box::new(Expression::Variable(Token::new(TokenType::Identifier, 1, "twice".into()), None)),
+                Token::new(TokenType::RightParen, 1, ")".into()),
+                args,
+            )
+        };
+        let arg = Expression::Primary(Token::of_numeric(Numeric(21f64), 1));
+        match e.eval_expr(&call(vec![arg])) {
+            Ok(EvalResult::Numeric { value, token: _ }) => assert_eq!(value, 42f64),
+            other => panic!("twice(21) should evaluate to 42, evaluated to {:?}", other),
+        }
+        match e.eval_expr(&call(vec![])) {
+            Err(super::Unwind::Error(err)) => {
+                assert_eq!(err.to_string(), "Expected 1 arguments but got 0.\n[Line 1]")
+            }
+            other => panic!("twice() should fail arity check, evaluated to {:?}", other),
+        }
+    }
+
+    #[test]
+    fn values_of_different_types_are_not_equal() {
+        use crate::parser::{Binary, Expression};
+        let mut e = Evaluator::new();
+        let operator = |s: &str, typ| Token::new(typ, 1, s.into());
+        for (op, binary, typ, expected) in [
+            ("!=", Binary::NotEqual, TokenType::BangEqual, true),
+            ("==", Binary::EqualEqual, TokenType::EqualEqual, false),
+        ] {
+            let expr = Expression::BinaryEx(
+                Box::new(Expression::Primary(Token::of_numeric(Numeric(1f64), 1))),
+                binary,
+                operator(op, typ),
+                Box::new(Expression::Primary(Token::of_string("a", 1))),
+            );
+            match e.eval_expr(&expr) {
+                Ok(EvalResult::Boolean { value, token }) => {
+                    assert_eq!(value, expected);
+                    assert_eq!(token.ln, 1);
+                }
+                other => panic!("1 {} \"a\" should evaluate to {}, got {:?}", op, expected, other),
+            }
+        }
+    }
+
     fn simple_eval_value(b: bool) {
         let expr = crate::parser::Expression::Primary(Token::of_bool(b, 1));
         let mut e = Evaluator::new();
-        match e.eval_expr(expr) {
-            Ok(EvalResult::Boolean { value, token }) => {
-                assert_eq!(value, b);
-                assert_eq!(
-                    token.typ,
-                    if b { TokenType::True } else { TokenType::False }
-                )
-            }
-            _ => (),
+        if let Ok(EvalResult::Boolean { value, token }) = e.eval_expr(&expr) {
+            assert_eq!(value, b);
+            assert_eq!(
+                token.typ,
+                if b { TokenType::True } else { TokenType::False }
+            )
         }
     }
 }
@@ -0,0 +1,283 @@
+/// Canonical formatting of Lox source. Works on tokens, comments and blank lines
+/// kept by the lexer, so the code itself (e.g. a for loop, which the parser
+/// desugars) is never rewritten, only the whitespace between tokens:
+/// - one statement per line, blocks indented by four spaces
+/// - opening brace on the line of the statement it belongs to, `} else` on one line
+/// - single space around binary operators and after commas, none inside parentheses
+/// - comments kept where they are, at most one blank line between statements
+use crate::{
+    interpreter::InterpreterError,
+    lexer::Lexer,
+    parser::Parser,
+    token::{Token, TokenType, Trivia, TriviaKind},
+    LexError,
+};
+
+const INDENT: &str = "    ";
+
+/// Formatted source; source with lex or syntax errors is not formatted
+pub fn format_source(source: &str) -> Result<String, InterpreterError> {
+    let mut lexer = Lexer::new(source);
+    let tokens = lexer.tokens();
+    let lex_errors = tokens
+        .iter()
+        .filter_map(LexError::from_token)
+        .collect::<Vec<_>>();
+    if !lex_errors.is_empty() {
+        return Err(InterpreterError::Lex(lex_errors));
+    }
+    let trivia = lexer.trivia().to_vec();
+    let prog = Parser::new(tokens.clone()).parse();
+    if !prog.syntax_errors().is_empty() {
+        return Err(InterpreterError::Parse(prog.syntax_errors().to_vec()));
+    }
+    Ok(Formatter::new(source).format(&tokens, &trivia))
+}
+
+/// Token or trivia, in source order
+enum Piece<'a> {
+    Token(&'a Token),
+    Trivia(&'a Trivia),
+}
+
+impl Piece<'_> {
+    fn start(&self) -> usize {
+        match self {
+            Piece::Token(t) => t.span.start,
+            Piece::Trivia(t) => t.span.start,
+        }
+    }
+
+    fn end(&self) -> usize {
+        match self {
+            Piece::Token(t) => t.span.end,
+            Piece::Trivia(t) => t.span.end,
+        }
+    }
+}
+
+/// token ends an operand, so minus following it is binary
+fn ends_operand(t: &TokenType) -> bool {
+    matches!(
+        t,
+        TokenType::Identifier
+            | TokenType::Number(_)
+            | TokenType::StringLiteral
+            | TokenType::True
+            | TokenType::False
+            | TokenType::Nil
+            | TokenType::This
+            | TokenType::RightParen
+    )
+}
+
+struct Formatter<'a> {
+    src: &'a str,
+    out: String,
+    indent: usize,
+    /// nesting of parentheses; semicolons inside them (for clauses) do not end line
+    parens: usize,
+    /// line break is written before next piece, unless it is a trailing comment
+    newline: bool,
+    /// blank line was seen since last written line break
+    blank: bool,
+    /// last token written and whether it was unary operator
+    prev: Option<(&'a Token, bool)>,
+    /// last piece written was opening brace
+    after_brace: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(src: &'a str) -> Self {
+        Formatter {
+            src,
+            out: String::new(),
+            indent: 0,
+            parens: 0,
+            newline: false,
+            blank: false,
+            prev: None,
+            after_brace: false,
+        }
+    }
+
+    fn format(mut self, tokens: &'a [Token], trivia: &'a [Trivia]) -> String {
+        let mut pieces = tokens
+            .iter()
+            .filter(|t| t.typ != TokenType::Eof)
+            .map(Piece::Token)
+            .chain(trivia.iter().map(Piece::Trivia))
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|p| p.start());
+
+        let mut prev_end = None;
+        for (i, piece) in pieces.iter().enumerate() {
+            let same_line =
+                prev_end.is_some_and(|end| !self.src[end..piece.start()].contains('\n'));
+            let next = pieces[i + 1..].iter().find_map(|p| match p {
+                Piece::Token(t) => Some(*t),
+                Piece::Trivia(_) => None,
+            });
+            match piece {
+                Piece::Token(t) => self.token(t, next),
+                Piece::Trivia(t) if t.kind == TriviaKind::BlankLine => self.blank = true,
+                Piece::Trivia(t) => {
+                    let next_on_same_line = pieces
+                        .get(i + 1)
+                        .is_some_and(|p| !self.src[piece.end()..p.start()].contains('\n'));
+                    self.comment(t, same_line, next_on_same_line)
+                }
+            }
+            if !matches!(piece, Piece::Trivia(t) if t.kind == TriviaKind::BlankLine) {
+                prev_end = Some(piece.end());
+            }
+        }
+        let len = self.out.trim_end().len();
+        self.out.truncate(len);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    /// writes pending line break, preceded by blank line if one was seen
+    /// (except at start of block and before its end)
+    fn break_line(&mut self, before_closing: bool) {
+        if self.newline {
+            self.out.push('\n');
+            if self.blank && !self.after_brace && !before_closing {
+                self.out.push('\n');
+            }
+            self.newline = false;
+        }
+        self.blank = false;
+    }
+
+    fn pad(&mut self, extra: usize) {
+        self.out.push_str(&INDENT.repeat(self.indent + extra));
+    }
+
+    /// statement is not complete (line break inside it was forced by comment)
+    fn continues_statement(&self) -> bool {
+        self.parens > 0
+            || self.prev.is_some_and(|(t, _)| {
+                !matches!(
+                    t.typ,
+                    TokenType::Semicolon | TokenType::LeftBrace | TokenType::RightBrace
+                )
+            })
+    }
+
+    /// starts line for piece if needed, otherwise writes space if requested
+    fn separate(&mut self, space: bool, before_closing: bool) {
+        self.break_line(before_closing);
+        if self.out.is_empty() {
+            return;
+        }
+        if self.out.ends_with('\n') {
+            let extra = usize::from(self.continues_statement() && !before_closing);
+            self.pad(extra);
+        } else if space && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    fn space_before(&self, t: &Token) -> bool {
+        let Some((prev, unary)) = self.prev else {
+            return false;
+        };
+        if unary {
+            return false;
+        }
+        !matches!(
+            (&prev.typ, &t.typ),
+            (
+                _,
+                TokenType::RightParen
+                    | TokenType::Comma
+                    | TokenType::Semicolon
+                    | TokenType::Dot
+                    | TokenType::Colon,
+            ) | (TokenType::LeftParen | TokenType::Dot, _)
+                | (TokenType::Identifier | TokenType::RightParen, TokenType::LeftParen)
+        )
+    }
+
+    fn token(&mut self, t: &'a Token, next: Option<&Token>) {
+        let text = &self.src[t.span.start..t.span.end];
+        let unary = match t.typ {
+            TokenType::Bang => true,
+            TokenType::Minus => !self.prev.is_some_and(|(p, _)| ends_operand(&p.typ)),
+            _ => false,
+        };
+        match t.typ {
+            TokenType::RightBrace => {
+                self.indent = self.indent.saturating_sub(1);
+                if self.after_brace {
+                    self.newline = false;
+                    self.blank = false;
+                    while self.out.ends_with(char::is_whitespace) {
+                        self.out.pop();
+                    }
+                } else {
+                    self.newline = !self.out.is_empty();
+                    self.separate(false, true);
+                }
+                self.out.push('}');
+                self.newline = !next.is_some_and(|n| n.typ == TokenType::Else);
+            }
+            _ => {
+                let space = self.space_before(t);
+                self.separate(space, false);
+                self.out.push_str(text);
+            }
+        }
+        match t.typ {
+            TokenType::LeftBrace => {
+                self.indent += 1;
+                self.newline = true;
+            }
+            TokenType::LeftParen => self.parens += 1,
+            TokenType::RightParen => self.parens = self.parens.saturating_sub(1),
+            TokenType::Semicolon if self.parens == 0 => self.newline = true,
+            _ => (),
+        }
+        self.after_brace = t.typ == TokenType::LeftBrace;
+        self.prev = Some((t, unary));
+    }
+
+    /// comment following previous piece on its line (trailing) or starting a line;
+    /// line comments end their line
+    fn comment(&mut self, c: &Trivia, same_line: bool, next_on_same_line: bool) {
+        let text = match c.kind {
+            TriviaKind::LineComment => format!("//{}", c.text.trim_end()),
+            TriviaKind::DocComment => format!("///{}", c.text.trim_end()),
+            _ => format!("/*{}*/", c.text),
+        };
+        // line break pending before the comment is kept for the piece after it
+        let pending = self.newline;
+        if same_line && !self.out.is_empty() {
+            self.newline = false;
+            if !self.out.ends_with(['(', ' ']) {
+                self.out.push(' ');
+            }
+        } else {
+            self.newline = !self.out.is_empty();
+            self.break_line(false);
+            if !self.out.is_empty() {
+                let extra = usize::from(self.continues_statement());
+                self.pad(extra);
+            }
+        }
+        self.out.push_str(&text);
+        if c.kind != TriviaKind::BlockComment || !next_on_same_line {
+            self.newline = true;
+        } else {
+            self.newline = pending && same_line;
+            if !self.newline {
+                self.out.push(' ');
+            }
+        }
+        self.after_brace = false;
+    }
+}
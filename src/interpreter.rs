@@ -0,0 +1,186 @@
+/// Public entry point for running Lox programs from Rust code.
+/// Runs the whole pipeline: lexer, parser, resolver and evaluator.
+use std::{error::Error, fmt::Display, io::Write, rc::Rc};
+
+use crate::{
+    diagnostics::{render, EvalError, LexError, ParseError},
+    evaluator::{
+        Argc, Argv, EvalResult, Evaluator, Hook, NativeFunction, StatementEvalResult, Unwind,
+    },
+    lexer::Lexer,
+    parser::{Parser, Program},
+    resolver::Resolver,
+};
+
+pub(crate) const RUNTIME_ERROR_CODE: u8 = 70u8;
+pub(crate) const PARSE_ERROR_CODE: u8 = 65u8;
+
+/// Failure of Interpreter::run_source, by phase it occurred in
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    /// one error per invalid token (unexpected character, unterminated string, ...)
+    Lex(Vec<LexError>),
+    /// syntax errors and static errors reported by resolver
+    Parse(Vec<ParseError>),
+    Runtime(EvalError),
+}
+
+impl InterpreterError {
+    /// 65 for lex and parse errors, 70 for runtime errors
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::Lex(_) | Self::Parse(_) => PARSE_ERROR_CODE,
+            Self::Runtime(_) => RUNTIME_ERROR_CODE,
+        }
+    }
+
+    /// error messages, each followed by the source line it points at and optional note
+    pub fn render(&self, source: &str) -> String {
+        let messages = match self {
+            Self::Lex(v) => v
+                .iter()
+                .map(|e| render(source, &e.to_string(), Some(&e.location), e.note.as_ref()))
+                .collect::<Vec<_>>(),
+            Self::Parse(v) => v
+                .iter()
+                .map(|e| render(source, &e.to_string(), Some(&e.location), e.note.as_ref()))
+                .collect(),
+            Self::Runtime(e) => {
+                vec![render(source, &e.to_string(), e.location.as_ref(), e.note.as_ref())]
+            }
+        };
+        messages.join("\n")
+    }
+}
+
+impl Error for InterpreterError {}
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lex(v) => {
+                let messages = v.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                f.write_str(&messages.join("\n"))
+            }
+            Self::Parse(v) => {
+                let messages = v.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+                f.write_str(&messages.join("\n"))
+            }
+            Self::Runtime(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+/// Lexes, parses and resolves source; the program is returned only if it has
+/// no lex, syntax or static errors
+pub(crate) fn check(source: &str) -> Result<Program, InterpreterError> {
+    let tokens = Lexer::new(source).tokens();
+    let lex_errors = tokens
+        .iter()
+        .filter_map(LexError::from_token)
+        .collect::<Vec<_>>();
+    if !lex_errors.is_empty() {
+        return Err(InterpreterError::Lex(lex_errors));
+    }
+    let mut prog = Parser::new(tokens).parse();
+    if !prog.syntax_errors().is_empty() {
+        return Err(InterpreterError::Parse(prog.syntax_errors().to_vec()));
+    }
+    let static_errors = Resolver::new().resolve(&mut prog);
+    if !static_errors.is_empty() {
+        return Err(InterpreterError::Parse(static_errors));
+    }
+    Ok(prog)
+}
+
+/// Runs Lox source code. Globals persist between runs.
+/// Output of print statements goes to `out`, error messages to `err`.
+pub struct Interpreter<'a> {
+    evaluator: Evaluator<'a>,
+    err: Box<dyn Write + 'a>,
+    /// code passed to `exit` by the last run
+    exit_status: Option<u8>,
+}
+
+impl Default for Interpreter<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Creates interpreter writing to stdout and stderr
+    pub fn new() -> Self {
+        Self::with_writers(std::io::stdout(), std::io::stderr())
+    }
+
+    /// Creates interpreter writing print output to out and error messages to err
+    pub fn with_writers(out: impl Write + 'a, err: impl Write + 'a) -> Self {
+        Interpreter {
+            evaluator: Evaluator::with_writer(Box::new(out)),
+            err: Box::new(err),
+            exit_status: None,
+        }
+    }
+
+    /// Runs program in source; on failure the error, followed by the offending
+    /// source line, is also written to err writer.
+    /// Program is not evaluated at all if it has lex or parse errors.
+    /// Program calling `exit` stops successfully, see exit_status
+    pub fn run_source(
+        &mut self,
+        source: &str,
+    ) -> Result<Vec<StatementEvalResult>, InterpreterError> {
+        let res = self.execute(source);
+        if let Err(e) = &res {
+            let _ = write!(self.err, "{}", e.render(source));
+        }
+        res
+    }
+
+    fn execute(&mut self, source: &str) -> Result<Vec<StatementEvalResult>, InterpreterError> {
+        self.exit_status = None;
+        let prog = check(source)?;
+        let mut results = Vec::new();
+        for r in self.evaluator.eval(prog) {
+            match r {
+                Ok(r) => results.push(r),
+                Err(Unwind::Exit(code)) => self.exit_status = Some(code),
+                Err(Unwind::Error(e)) => return Err(InterpreterError::Runtime(e)),
+            }
+        }
+        Ok(results)
+    }
+
+    /// code passed to `exit` by the program of the last run (None if it did not call it)
+    pub fn exit_status(&self) -> Option<u8> {
+        self.exit_status
+    }
+
+    /// hook is called before each declaration and statement evaluated
+    pub(crate) fn set_hook(&mut self, hook: Box<dyn Hook + 'a>) {
+        self.evaluator.set_hook(hook);
+    }
+
+    /// makes args available to programs through `argc()` and `argv(i)`;
+    /// by convention the first one is script name
+    pub fn set_args(&mut self, args: Vec<String>) {
+        let args = Rc::new(args);
+        self.define_native(Rc::new(Argc(args.clone())));
+        self.define_native(Rc::new(Argv(args)));
+    }
+
+    /// value of global variable
+    pub fn get_global(&self, name: &str) -> Option<EvalResult> {
+        self.evaluator.get_global(name)
+    }
+
+    /// defines (or redefines) global variable visible to subsequent runs
+    pub fn define_global(&mut self, name: &str, value: EvalResult) {
+        self.evaluator.define_global(name, value)
+    }
+
+    /// makes native function callable from Lox under its name
+    pub fn define_native(&mut self, native: Rc<dyn NativeFunction>) {
+        self.evaluator.define_native(native)
+    }
+}
@@ -0,0 +1,244 @@
+/// Minimal JSON document model used by `--format json` output of the commands,
+/// and conversions of tokens, values and diagnostics to it (syntax trees are
+/// converted by ast_dump).
+use std::fmt::{Display, Write};
+
+use crate::{
+    diagnostics::{EvalError, LexError, Location, ParseError, Warning},
+    evaluator::EvalResult,
+    interpreter::InterpreterError,
+    token::{Token, TokenType},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    /// keys keep insertion order
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub(crate) fn object<const N: usize>(fields: [(&str, Json); N]) -> Json {
+        Json::Object(
+            fields
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+        )
+    }
+
+    /// appends field to object; other values are left unchanged
+    pub(crate) fn with(mut self, key: &str, value: Json) -> Json {
+        if let Json::Object(fields) = &mut self {
+            fields.push((key.to_string(), value));
+        }
+        self
+    }
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Self {
+        Json::String(s.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(s: String) -> Self {
+        Json::String(s)
+    }
+}
+
+impl From<bool> for Json {
+    fn from(b: bool) -> Self {
+        Json::Bool(b)
+    }
+}
+
+impl From<f64> for Json {
+    fn from(n: f64) -> Self {
+        Json::Number(n)
+    }
+}
+
+impl From<u64> for Json {
+    fn from(n: u64) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<usize> for Json {
+    fn from(n: usize) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl From<u8> for Json {
+    fn from(n: u8) -> Self {
+        Json::Number(n as f64)
+    }
+}
+
+impl<T: Into<Json>> From<Option<T>> for Json {
+    fn from(o: Option<T>) -> Self {
+        o.map_or(Json::Null, Into::into)
+    }
+}
+
+impl<T: Into<Json>> From<Vec<T>> for Json {
+    fn from(v: Vec<T>) -> Self {
+        Json::Array(v.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_escaped(f: &mut std::fmt::Formatter<'_>, s: &str) -> std::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// compact JSON text; numbers without fraction are written as integers,
+/// NaN and infinities (not representable in JSON) as null
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) if !n.is_finite() => f.write_str("null"),
+            Json::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 => write!(f, "{}", *n as i64),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(s) => write_escaped(f, s),
+            Json::Array(v) => {
+                f.write_char('[')?;
+                for (i, e) in v.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", e)?;
+                }
+                f.write_char(']')
+            }
+            Json::Object(fields) => {
+                f.write_char('{')?;
+                for (i, (k, v)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_escaped(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// literal value of token: number, string or null
+fn literal(t: &Token) -> Json {
+    match &t.typ {
+        TokenType::Number(n) => Json::Number(n.0),
+        TokenType::StringLiteral => Json::from(t.s.as_str()),
+        _ => Json::Null,
+    }
+}
+
+/// column is null for locations not read from source
+fn column(l: &Location) -> Json {
+    Some(l.span.col).filter(|_| l.span.is_known()).into()
+}
+
+pub(crate) fn token(t: &Token) -> Json {
+    Json::object([
+        ("type", t.typ.name().into()),
+        ("lexeme", t.s.as_str().into()),
+        ("literal", literal(t)),
+        ("line", t.ln.into()),
+        ("column", column(&Location::of(t))),
+    ])
+}
+
+fn diagnostic(phase: &str, code: &str, message: String, l: Option<&Location>) -> Json {
+    Json::object([
+        ("kind", phase.into()),
+        ("code", code.into()),
+        ("message", message.into()),
+        ("line", l.map(|l| l.line).into()),
+        ("column", l.map_or(Json::Null, column)),
+    ])
+}
+
+pub(crate) fn lex_error(e: &LexError) -> Json {
+    diagnostic("lex", e.code(), e.kind.to_string(), Some(&e.location))
+        .with("note", e.note.clone().into())
+}
+
+pub(crate) fn parse_error(e: &ParseError) -> Json {
+    diagnostic("parse", e.code(), e.kind.to_string(), Some(&e.location))
+        .with("lexeme", e.lexeme.clone().into())
+        .with("note", e.note.clone().into())
+}
+
+pub(crate) fn eval_error(e: &EvalError) -> Json {
+    diagnostic("runtime", e.code(), e.kind.to_string(), e.location.as_ref())
+        .with("note", e.note.clone().into())
+}
+
+pub(crate) fn warning(w: &Warning) -> Json {
+    diagnostic("lint", w.code(), w.kind.to_string(), Some(&w.location))
+        .with("name", w.kind.name().into())
+}
+
+/// one diagnostic per error
+pub(crate) fn interpreter_error(e: &InterpreterError) -> Vec<Json> {
+    match e {
+        InterpreterError::Lex(v) => v.iter().map(lex_error).collect(),
+        InterpreterError::Parse(v) => v.iter().map(parse_error).collect(),
+        InterpreterError::Runtime(e) => vec![eval_error(e)],
+    }
+}
+
+/// value with its Lox type; functions, classes and instances are given by their text form
+pub(crate) fn value(v: &EvalResult) -> Json {
+    let value = match v {
+        EvalResult::Numeric { value, .. } => Json::Number(*value),
+        EvalResult::Boolean { value, .. } => Json::Bool(*value),
+        EvalResult::String { value, .. } => value.as_str().into(),
+        EvalResult::Reserved { .. } | EvalResult::Nil => Json::Null,
+        other => other.to_string().into(),
+    };
+    Json::object([("type", v.type_name().into()), ("value", value)])
+}
+
+#[cfg(test)]
+mod json_test {
+    use super::Json;
+
+    #[test]
+    fn escapes_strings_and_keeps_field_order() {
+        let j = Json::object([
+            ("b", "say \"hi\"\n\u{1}".into()),
+            ("a", Json::Array(vec![Json::Null, true.into(), 1.5.into()])),
+        ]);
+        assert_eq!(j.to_string(), r#"{"b":"say \"hi\"\n\u0001","a":[null,true,1.5]}"#);
+    }
+
+    #[test]
+    fn integral_numbers_have_no_fraction() {
+        assert_eq!(Json::from(3.0).to_string(), "3");
+        assert_eq!(Json::from(-0.25).to_string(), "-0.25");
+        assert_eq!(Json::from(f64::NAN).to_string(), "null");
+    }
+}
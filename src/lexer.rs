@@ -1,48 +1,160 @@
 use crate::token::LexicalError;
 use crate::token::Numeric;
-use crate::token::{Token, TokenType};
-use std::{iter::Peekable, str::Chars};
+use crate::token::{Span, Token, TokenType, Trivia, TriviaKind};
+use std::{iter::Peekable, str::CharIndices};
 pub type LineNum = u64;
 
+/// All comments and blank lines in source, in source order
+/// (for tooling: doc extraction, formatting)
+pub fn trivia(source: &str) -> Vec<Trivia> {
+    let mut lexer = Lexer::new(source);
+    lexer.by_ref().for_each(drop);
+    lexer.trivia().to_vec()
+}
+
 pub(crate) struct Lexer<'a> {
-    iter: Peekable<Chars<'a>>,
+    src: &'a str,
+    iter: Peekable<CharIndices<'a>>,
     at_end: bool,
     line: LineNum,
+    /// byte offset of first character of current line
+    line_start: usize,
+    /// byte offset and column of first character of token being scanned
+    start: usize,
+    start_col: usize,
+    /// byte offset on current line and its column; columns of later offsets are
+    /// counted from here, so each line is counted once
+    counted: usize,
+    counted_col: usize,
+    /// comments and blank lines skipped so far, in source order
+    trivia: Vec<Trivia>,
 }
 
 impl<'a> Lexer<'a> {
     pub(crate) fn new(s: &'a str) -> Self {
         Lexer {
-            iter: s.chars().peekable(),
+            src: s,
+            iter: s.char_indices().peekable(),
             at_end: false,
             line: 1,
+            line_start: 0,
+            start: 0,
+            start_col: 1,
+            counted: 0,
+            counted_col: 1,
+            trivia: Vec::new(),
         }
     }
     pub(crate) fn tokens(&mut self) -> Vec<Token> {
         self.into_iter().collect()
     }
 
+    /// comments and blank lines skipped by the tokens scanned so far
+    pub(crate) fn trivia(&self) -> &[Trivia] {
+        &self.trivia
+    }
+
+    /// remembers where the token being scanned starts
+    fn mark_start(&mut self, offset: usize) {
+        self.counted_col += self.src[self.counted..offset].chars().count();
+        self.counted = offset;
+        self.start = offset;
+        self.start_col = self.counted_col;
+    }
+
+    /// newline at byte offset starts next line
+    fn new_line(&mut self, offset: usize) {
+        self.line += 1;
+        self.line_start = offset + 1;
+        self.counted = self.line_start;
+        self.counted_col = 1;
+    }
+
+    /// byte offset of next character to scan
+    fn pos(&mut self) -> usize {
+        self.iter.peek().map_or(self.src.len(), |(i, _)| *i)
+    }
+
+    /// slash, or comment starting with it followed by next token
     fn match_or_skip(&mut self) -> Option<Token> {
-        let p = &mut self.iter;
-        let next = p.peek();
-        let token = |ch: &char| Some(Token::new(TokenType::Slash, self.line, ch.to_string()));
-        match next {
-            Some(w) if *w != '/' => token(w),
-            None => token(&'/'),
-            _ => loop {
-                match p.next() {
-                    None => {
-                        self.at_end = true;
-                        break Some(Token::new(TokenType::Eof, self.line, "".to_string()));
-                    }
-                    Some('\n') => {
-                        self.line += 1;
-                        break self.next();
-                    }
-                    _ => continue,
+        match self.iter.peek().map(|(_, c)| *c) {
+            Some('/') => {
+                self.line_comment();
+                self.scan()
+            }
+            Some('*') => self.block_comment(),
+            _ => Some(Token::new(TokenType::Slash, self.line, "/".to_string())),
+        }
+    }
+
+    fn push_trivia(&mut self, kind: TriviaKind, ln: LineNum, text: &str) {
+        let span = Span {
+            start: self.start,
+            end: self.pos(),
+            col: self.start_col,
+        };
+        self.trivia.push(Trivia {
+            kind,
+            ln,
+            text: text.to_string(),
+            span,
+        });
+    }
+
+    /// records line ending with newline at byte offset if it has only whitespace
+    fn blank_line(&mut self, offset: usize) {
+        let text = &self.src[self.line_start..offset];
+        if text.trim().is_empty() {
+            self.trivia.push(Trivia {
+                kind: TriviaKind::BlankLine,
+                ln: self.line,
+                text: text.trim_end_matches('\r').to_string(),
+                span: Span {
+                    start: self.line_start,
+                    end: offset,
+                    col: 1,
+                },
+            });
+        }
+    }
+
+    /// skips comment up to end of line; `///` (but not `////`) starts doc comment
+    fn line_comment(&mut self) {
+        let rest = &self.src[self.start..];
+        let (kind, prefix) = if rest.starts_with("///") && !rest.starts_with("////") {
+            (TriviaKind::DocComment, 3)
+        } else {
+            (TriviaKind::LineComment, 2)
+        };
+        while self.iter.next_if(|(_, c)| *c != '\n').is_some() {}
+        let text = &self.src[self.start + prefix..self.pos()];
+        self.push_trivia(kind, self.line, text.trim_end_matches('\r'));
+    }
+
+    /// skips `/* ... */` comment, which may contain nested block comments,
+    /// and scans the token after it
+    fn block_comment(&mut self) -> Option<Token> {
+        let ln = self.line;
+        self.iter.next();
+        let mut depth = 1;
+        while depth > 0 {
+            match self.iter.next() {
+                None => {
+                    return Some(Token::new(
+                        TokenType::Unknown(LexicalError::UnterminatedBlockComment),
+                        ln,
+                        "/*".to_string(),
+                    ))
                 }
-            },
+                Some((i, '\n')) => self.new_line(i),
+                Some((_, '/')) if self.iter.next_if(|(_, c)| *c == '*').is_some() => depth += 1,
+                Some((_, '*')) if self.iter.next_if(|(_, c)| *c == '/').is_some() => depth -= 1,
+                _ => (),
+            }
         }
+        let text = &self.src[self.start + 2..self.pos() - 2];
+        self.push_trivia(TriviaKind::BlockComment, ln, text);
+        self.scan()
     }
 
     fn match_next(&mut self, c: char, matching: Token, other: Token) -> Option<Token> {
@@ -50,7 +162,7 @@ impl<'a> Lexer<'a> {
 
         let next = p.peek();
         match next {
-            Some(w) if *w == c => {
+            Some((_, w)) if *w == c => {
                 p.next();
                 Some(matching)
             }
@@ -61,23 +173,22 @@ impl<'a> Lexer<'a> {
 
     fn parse_string(&mut self) -> Option<Token> {
         let mut literal = String::new();
-        let p = &mut self.iter;
         let unknown = |s: String, l: LineNum| {
             Token::new(TokenType::Unknown(LexicalError::UnterminatedString), l, s)
         };
 
         loop {
-            match p.next() {
-                Some('\"') => break Some(Token::new(TokenType::StringLiteral, self.line, literal)),
-                Some('\n') => {
-                    self.line += 1;
+            match self.iter.next() {
+                Some((_, '\"')) => break Some(Token::new(TokenType::StringLiteral, self.line, literal)),
+                Some((i, '\n')) => {
+                    self.new_line(i);
                     //break Some(unknown(literal, self.line));
                     literal.push('\n')
                 }
                 None => {
                     break Some(unknown(literal, self.line));
                 }
-                Some(c) => literal.push(c),
+                Some((_, c)) => literal.push(c),
             }
         }
     }
@@ -100,7 +211,7 @@ impl<'a> Lexer<'a> {
     fn parse_number(&mut self, first: char) -> Option<Token> {
         let mut val_str = String::from(first);
         let p = &mut self.iter;
-        let mut curr = p.peek();
+        let mut curr = p.peek().map(|(_, c)| c);
         loop {
             match curr {
                 Some(c) if c.is_ascii_digit() => val_str.push(*c),
@@ -121,7 +232,7 @@ impl<'a> Lexer<'a> {
                 _ => break self.try_parse(&val_str),
             }
             p.next();
-            curr = p.peek();
+            curr = p.peek().map(|(_, c)| c);
         }
     }
     /// Returns Some(c) where c is a token representing a reserved word
@@ -129,7 +240,9 @@ impl<'a> Lexer<'a> {
     fn reserved_from_str(&self, s: &str) -> Option<Token> {
         let tokentype = match s {
             "and" => Some(TokenType::And),
+            "break" => Some(TokenType::Break),
             "class" => Some(TokenType::Class),
+            "continue" => Some(TokenType::Continue),
             "else" => Some(TokenType::Else),
             "false" => Some(TokenType::False),
             "for" => Some(TokenType::For),
@@ -152,7 +265,7 @@ impl<'a> Lexer<'a> {
     fn parse_ident(&mut self, first: char) -> Option<Token> {
         let mut val_str = String::from(first);
         let p = &mut self.iter;
-        let mut curr = p.peek();
+        let mut curr = p.peek().map(|(_, c)| c);
         loop {
             match curr {
                 Some(c) if c.is_ascii_alphanumeric() || *c == '_' => val_str.push(*c),
@@ -166,17 +279,14 @@ impl<'a> Lexer<'a> {
                 }
             }
             p.next();
-            curr = p.peek();
+            curr = p.peek().map(|(_, c)| c);
         }
     }
-}
 
-impl<'a> Iterator for Lexer<'a> {
-    type Item = Token;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let p = &mut self.iter;
-        if let Some(c) = p.next() {
+    /// scans next token; its span is set by Iterator::next
+    fn scan(&mut self) -> Option<Token> {
+        if let Some((i, c)) = self.iter.next() {
+            self.mark_start(i);
             match c {
                 '(' => Some(Token::of_char(TokenType::LeftParen, self.line, c)),
                 ')' => Some(Token::of_char(TokenType::RightParen, self.line, c)),
@@ -189,6 +299,7 @@ impl<'a> Iterator for Lexer<'a> {
                 '.' => Some(Token::of_char(TokenType::Dot, self.line, c)),
                 ',' => Some(Token::of_char(TokenType::Comma, self.line, c)),
                 ';' => Some(Token::of_char(TokenType::Semicolon, self.line, c)),
+                ':' => Some(Token::of_char(TokenType::Colon, self.line, c)),
                 '=' => self.match_next(
                     '=',
                     Token::new(TokenType::EqualEqual, self.line, "==".into()),
@@ -212,10 +323,11 @@ impl<'a> Iterator for Lexer<'a> {
                 '/' => self.match_or_skip(),
                 '\"' => self.parse_string(),
                 '\n' => {
-                    self.line += 1;
-                    self.next()
+                    self.blank_line(i);
+                    self.new_line(i);
+                    self.scan()
                 }
-                sp if sp.is_ascii_whitespace() => self.next(),
+                sp if sp.is_ascii_whitespace() => self.scan(),
                 d if d.is_ascii_digit() || d == '.' => self.parse_number(d),
                 a if a.is_ascii_alphabetic() || a == '_' => self.parse_ident(a),
                 unknown => Some(Token::new(
@@ -226,9 +338,24 @@ impl<'a> Iterator for Lexer<'a> {
             }
         } else if !self.at_end {
             self.at_end = true;
+            self.mark_start(self.src.len());
             Some(Token::new(TokenType::Eof, self.line, "".into()))
         } else {
             None
         }
     }
 }
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.scan()?;
+        let span = Span {
+            start: self.start,
+            end: self.pos(),
+            col: self.start_col,
+        };
+        Some(token.with_span(span))
+    }
+}
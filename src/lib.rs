@@ -0,0 +1,44 @@
+//! Tree-walking interpreter of the Lox language.
+//!
+//! Embed it with [`Interpreter`]:
+//! ```
+//! use codecrafters_interpreter::Interpreter;
+//!
+//! let mut out = Vec::new();
+//! let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+//! interpreter.run_source("var a = 1; print a + 1;").unwrap();
+//! assert_eq!(interpreter.get_global("a").unwrap().to_string(), "1");
+//! drop(interpreter);
+//! assert_eq!(String::from_utf8(out).unwrap(), "2\n");
+//! ```
+pub mod cli;
+mod ast_dump;
+mod conformance;
+mod debugger;
+mod diagnostics;
+mod environment;
+mod evaluator;
+mod formatter;
+mod interpreter;
+mod json;
+mod lexer;
+mod linter;
+mod parser;
+mod repl;
+mod resolver;
+#[cfg(test)]
+mod tests;
+mod token;
+mod unparser;
+pub mod utils;
+
+pub use diagnostics::{
+    EvalError, LexError, Location, ParseError, ParseErrorKind, RuntimeErrorKind, Warning,
+    WarningKind,
+};
+pub use evaluator::{EvalResult, NativeFunction, StatementEvalResult, Unwind};
+pub use formatter::format_source;
+pub use interpreter::{Interpreter, InterpreterError};
+pub use lexer::trivia;
+pub use token::{LexicalError, Numeric, Span, Token, TokenType, Trivia, TriviaKind};
+pub use unparser::unparse_source;
@@ -0,0 +1,311 @@
+/// Static checks reporting suspicious, but valid code without running it.
+/// Runs on programs without lex, syntax and static errors.
+///
+/// A warning is suppressed by a line comment directive listing its code or name,
+/// e.g. `// lint: allow W001 no-effect`, placed at the end of the line of the warning
+/// or alone on the line before it.
+use crate::{
+    diagnostics::{Location, Warning, WarningKind},
+    interpreter::{check, InterpreterError},
+    lexer::{self, LineNum},
+    parser::{Binary, Class, Decl, Expression, Function, Program, Stmt},
+    token::{Token, TokenType, TriviaKind},
+};
+
+/// Warnings for source in source order, without suppressed ones
+pub(crate) fn lint(source: &str) -> Result<Vec<Warning>, InterpreterError> {
+    let prog = check(source)?;
+    let allowed = directives(source);
+    let mut warnings = Linter::new().lint(&prog);
+    warnings.retain(|w| !is_allowed(&allowed, w));
+    Ok(warnings)
+}
+
+/// line each `lint: allow` directive applies to and codes or names it lists
+fn directives(source: &str) -> Vec<(LineNum, Vec<String>)> {
+    lexer::trivia(source)
+        .into_iter()
+        .filter(|t| t.kind == TriviaKind::LineComment)
+        .filter_map(|t| {
+            let rest = t.text.trim().strip_prefix("lint:")?.trim_start();
+            let list = rest.strip_prefix("allow")?;
+            let names = list
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+                .collect();
+            let line_start = source[..t.span.start].rfind('\n').map_or(0, |i| i + 1);
+            let alone = source[line_start..t.span.start].trim().is_empty();
+            Some((if alone { t.ln + 1 } else { t.ln }, names))
+        })
+        .collect()
+}
+
+fn is_allowed(directives: &[(LineNum, Vec<String>)], w: &Warning) -> bool {
+    directives.iter().any(|(ln, names)| {
+        *ln == w.location.line && names.iter().any(|n| n == w.kind.code() || n == w.kind.name())
+    })
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LocalKind {
+    Var,
+    Param,
+    Fun,
+    Class,
+}
+
+struct Local {
+    name: Token,
+    kind: LocalKind,
+    used: bool,
+}
+
+struct Linter {
+    /// global scope followed by local scopes; globals are tracked only for shadowing
+    scopes: Vec<Vec<Local>>,
+    warnings: Vec<Warning>,
+}
+
+/// location of expression; expressions without span are located at line 0
+fn location(e: &Expression) -> Location {
+    Location {
+        line: e.line().unwrap_or_default(),
+        span: e.span().unwrap_or_default(),
+    }
+}
+
+/// evaluating expression has no side effect (and calls no code)
+fn is_pure(e: &Expression) -> bool {
+    match e {
+        Expression::Primary(_) | Expression::Variable(..) | Expression::This(..) => true,
+        Expression::Super(..) => true,
+        Expression::Get(o, _) | Expression::UnaryEx(_, _, o) | Expression::Paren(_, o, _) => {
+            is_pure(o)
+        }
+        Expression::BinaryEx(l, .., r) | Expression::LogicalEx(l, .., r) => {
+            is_pure(l) && is_pure(r)
+        }
+        Expression::Call(..) | Expression::Set(..) | Expression::Assign(..) => false,
+        Expression::Invalid => false,
+    }
+}
+
+/// type name of literal expression
+fn literal_type(e: &Expression) -> Option<&'static str> {
+    match e {
+        Expression::Primary(t) => match t.typ {
+            TokenType::Number(_) => Some("number"),
+            TokenType::StringLiteral => Some("string"),
+            TokenType::True | TokenType::False => Some("boolean"),
+            TokenType::Nil => Some("nil"),
+            _ => None,
+        },
+        Expression::Paren(_, e, _) => literal_type(e),
+        _ => None,
+    }
+}
+
+/// text of object expression, if it always denotes the same value
+fn object_name(e: &Expression) -> Option<&str> {
+    match e {
+        Expression::Variable(t, _) => Some(&t.s),
+        Expression::This(..) => Some("this"),
+        _ => None,
+    }
+}
+
+impl Linter {
+    fn new() -> Self {
+        Linter {
+            scopes: vec![Vec::new()],
+            warnings: Vec::new(),
+        }
+    }
+
+    fn lint(mut self, p: &Program) -> Vec<Warning> {
+        for d in p.declarations.iter() {
+            self.lint_decl(d);
+        }
+        self.warnings
+            .sort_by_key(|w| (w.location.line, w.location.span.start));
+        self.warnings
+    }
+
+    fn warn(&mut self, kind: WarningKind, location: Location) {
+        self.warnings.push(Warning { kind, location });
+    }
+
+    fn lint_decl(&mut self, d: &Decl) {
+        match d {
+            Decl::VarDecl(t, init) => {
+                if let Some(e) = init {
+                    self.lint_expr(e);
+                }
+                if self.is_shadowing(t) {
+                    self.warn(WarningKind::ShadowedVariable(t.s.clone()), Location::of(t));
+                }
+                self.declare(t, LocalKind::Var);
+            }
+            Decl::Fun(f) => {
+                self.declare(&f.name, LocalKind::Fun);
+                self.lint_function(f);
+            }
+            Decl::Class(c) => self.lint_class(c),
+            Decl::Statement(s) => self.lint_stmt(s),
+        }
+    }
+
+    fn lint_class(&mut self, c: &Class) {
+        self.declare(&c.name, LocalKind::Class);
+        if let Some(sc) = &c.superclass {
+            self.lint_expr(sc);
+        }
+        for m in c.methods.iter() {
+            self.lint_function(m);
+        }
+    }
+
+    fn lint_function(&mut self, f: &Function) {
+        self.scopes.push(Vec::new());
+        for p in f.params.iter() {
+            self.declare(p, LocalKind::Param);
+        }
+        for d in f.body.iter() {
+            self.lint_decl(d);
+        }
+        self.end_scope();
+    }
+
+    fn lint_stmt(&mut self, s: &Stmt) {
+        match s {
+            Stmt::Expression(e) => {
+                if is_pure(e) {
+                    self.warn(WarningKind::NoEffect, location(e));
+                }
+                self.lint_expr(e);
+            }
+            Stmt::Print(_, e) => self.lint_expr(e),
+            Stmt::Block(v) => {
+                self.scopes.push(Vec::new());
+                for d in v.iter() {
+                    self.lint_decl(d);
+                }
+                self.end_scope();
+            }
+            Stmt::If(_, c, t, e) => {
+                self.lint_expr(c);
+                self.lint_stmt(t);
+                if let Some(e) = e {
+                    self.lint_stmt(e);
+                }
+            }
+            Stmt::While(_, c, b, _) => {
+                self.lint_expr(c);
+                self.lint_stmt(b);
+            }
+            Stmt::Return(_, Some(e)) => self.lint_expr(e),
+            Stmt::Return(_, None) | Stmt::Break(..) | Stmt::Continue(..) | Stmt::Invalid => (),
+        }
+    }
+
+    fn lint_expr(&mut self, e: &Expression) {
+        match e {
+            Expression::Variable(t, _) => self.mark_used(t),
+            Expression::Assign(t, value, _) => {
+                if let Expression::Variable(v, _) = value.as_ref() {
+                    if v.s == t.s {
+                        self.warn(WarningKind::SelfAssignment(t.s.clone()), location(e));
+                    }
+                }
+                self.lint_expr(value);
+            }
+            Expression::Set(object, name, value) => {
+                if let Expression::Get(o, n) = value.as_ref() {
+                    let same = object_name(object).is_some_and(|a| object_name(o) == Some(a));
+                    if same && n.s == name.s {
+                        let target = format!("{}.{}", object_name(object).unwrap(), name.s);
+                        self.warn(WarningKind::SelfAssignment(target), location(e));
+                    }
+                }
+                self.lint_expr(object);
+                self.lint_expr(value);
+            }
+            Expression::BinaryEx(l, op, _, r) => {
+                if matches!(op, Binary::EqualEqual | Binary::NotEqual) {
+                    if let (Some(left), Some(right)) = (literal_type(l), literal_type(r)) {
+                        if left != right {
+                            let kind = WarningKind::LiteralTypeMismatch {
+                                left: left.to_string(),
+                                right: right.to_string(),
+                                result: *op == Binary::NotEqual,
+                            };
+                            self.warn(kind, location(e));
+                        }
+                    }
+                }
+                self.lint_expr(l);
+                self.lint_expr(r);
+            }
+            Expression::LogicalEx(l, .., r) => {
+                self.lint_expr(l);
+                self.lint_expr(r);
+            }
+            Expression::UnaryEx(_, _, e) | Expression::Paren(_, e, _) | Expression::Get(e, _) => {
+                self.lint_expr(e)
+            }
+            Expression::Call(c, _, args) => {
+                self.lint_expr(c);
+                for a in args.iter() {
+                    self.lint_expr(a);
+                }
+            }
+            Expression::Primary(_)
+            | Expression::This(..)
+            | Expression::Super(..)
+            | Expression::Invalid => (),
+        }
+    }
+
+    fn declare(&mut self, t: &Token, kind: LocalKind) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(Local {
+                name: t.clone(),
+                kind,
+                used: false,
+            });
+        }
+    }
+
+    /// variable declared by t shadows variable declared in an enclosing scope
+    fn is_shadowing(&self, t: &Token) -> bool {
+        let outer = &self.scopes[..self.scopes.len() - 1];
+        outer
+            .iter()
+            .flatten()
+            .any(|l| l.kind == LocalKind::Var && l.name.s == t.s)
+    }
+
+    /// marks innermost variable named as t as read
+    fn mark_used(&mut self, t: &Token) {
+        let local = self
+            .scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.iter_mut().rev().find(|l| l.name.s == t.s));
+        if let Some(l) = local {
+            l.used = true;
+        }
+    }
+
+    /// leaves local scope, reporting its variables that were never read
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap_or_default();
+        for l in scope {
+            if l.kind == LocalKind::Var && !l.used && !l.name.s.starts_with('_') {
+                let kind = WarningKind::UnusedVariable(l.name.s.clone());
+                self.warn(kind, Location::of(&l.name));
+            }
+        }
+    }
+}
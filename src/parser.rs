@@ -1,6 +1,17 @@
+use crate::diagnostics::{ParseError, ParseErrorKind};
+use crate::lexer::LineNum;
 use crate::token;
 use core::fmt::Display;
-use token::{Token, TokenType};
+use std::rc::Rc;
+use token::{Span, Token, TokenType};
+
+/// Maximal number of function parameters and call arguments
+const MAX_ARGS: usize = 255;
+
+/// Deepest nesting of expressions and statements; deeper source is a syntax error
+/// instead of overflowing the native stack of the parser or later passes
+/// (the limit fits a default 2 MiB thread stack also in debug builds)
+const MAX_NESTING: usize = 100;
 
 /// Parser for lox.
 /// Initialized with a vector of tokens.
@@ -8,20 +19,139 @@ use token::{Token, TokenType};
 pub(crate) struct Parser {
     tokens: Vec<Token>,
     curr: usize,
+    /// labels of loops enclosing current statement (None for unlabeled loop)
+    loops: Vec<Option<String>>,
+    /// labels of all loops parsed so far; labels made up by for loop lowering avoid them
+    labels: Vec<String>,
+    /// number of function declarations enclosing current statement
+    functions: usize,
+    /// number of blocks enclosing current declaration
+    blocks: usize,
+    /// number of expressions and statements enclosing the one being parsed
+    nesting: usize,
+    /// syntax errors in source order
+    errors: Vec<ParseError>,
+    /// set after a syntax error until parser resynchronizes at next statement;
+    /// errors found meanwhile are not reported
+    panic: bool,
 }
 
-///Declaration can be variable declaration or a statement
+///Declaration can be variable declaration, function declaration or a statement
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Decl {
     VarDecl(Token, Option<Expression>),
+    Fun(Rc<Function>),
+    Class(Rc<Class>),
     Statement(Stmt),
 }
+/// Function declaration: name, parameters and body
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Function {
+    pub name: Token,
+    pub params: Vec<Token>,
+    pub body: Vec<Decl>,
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let params = self.params.iter().map(|p| p.s.clone()).collect::<Vec<_>>();
+        write!(f, "(fun {} ({}) (block", self.name.s, params.join(" "))?;
+        self.body.iter().try_for_each(|d| write!(f, " {}", d))?;
+        f.write_str("))")
+    }
+}
+
+/// Class declaration: name, optional superclass (always Expression::Variable) and methods
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Class {
+    pub name: Token,
+    pub superclass: Option<Expression>,
+    pub methods: Vec<Rc<Function>>,
+}
+
+impl Display for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "(class {}", self.name.s)?;
+        if let Some(sc) = &self.superclass {
+            write!(f, " < {}", sc)?;
+        }
+        self.methods.iter().try_for_each(|m| write!(f, " {}", m))?;
+        f.write_str(")")
+    }
+}
+
+/// span of token read from source
+fn known(t: &Token) -> Option<Span> {
+    Some(t.span).filter(|s| s.is_known())
+}
+
+/// span covering both optional spans
+fn join(a: Option<Span>, b: Option<Span>) -> Option<Span> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.to(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Kind of error reporting missing token or construct described by what
+fn expect(what: &str) -> ParseErrorKind {
+    ParseErrorKind::Expect(what.to_string())
+}
+
+/// Calls f on each break and continue in body s of loop labeled with label that
+/// leaves or continues this loop; nested says if s is inside a loop nested in it
+fn retarget(s: &mut Stmt, label: Option<&str>, nested: bool, f: &mut impl FnMut(&mut Stmt)) {
+    match s {
+        Stmt::Break(_, l) | Stmt::Continue(_, l) => {
+            let targets = match l {
+                Some(l) => Some(l.s.as_str()) == label,
+                None => !nested,
+            };
+            if targets {
+                f(s);
+            }
+        }
+        Stmt::Block(v) => v.iter_mut().for_each(|d| {
+            if let Decl::Statement(s) = d {
+                retarget(s, label, nested, f)
+            }
+        }),
+        Stmt::If(_, _, t, e) => {
+            retarget(t, label, nested, f);
+            if let Some(e) = e {
+                retarget(e, label, nested, f);
+            }
+        }
+        // loop with the same label hides the outer one
+        Stmt::While(_, _, b, l) if label.is_none() || l.as_ref().map(|l| &l.s[..]) != label => {
+            retarget(b, label, true, f)
+        }
+        _ => (),
+    }
+}
+
 impl Decl {
-    fn is_valid(&self) -> bool {
+    /// line where declaration starts (None for statements without one, e.g. blocks)
+    pub(crate) fn line(&self) -> Option<LineNum> {
+        match self {
+            Decl::VarDecl(t, _) => Some(t.ln),
+            Decl::Fun(f) => Some(f.name.ln),
+            Decl::Class(c) => Some(c.name.ln),
+            Decl::Statement(s) => s.line(),
+        }
+    }
+
+    /// source range from first to last token stored in this declaration
+    pub(crate) fn span(&self) -> Option<Span> {
         match self {
-            Decl::VarDecl(_token, Some(e)) => e.is_valid(),
-            Decl::VarDecl(_token, None) => true,
-            Decl::Statement(stmt) => stmt.is_valid(),
+            Decl::VarDecl(t, e) => join(known(t), e.as_ref().and_then(|e| e.span())),
+            Decl::Fun(f) => join(known(&f.name), f.body.last().and_then(|d| d.span())),
+            Decl::Class(c) => {
+                let last_method = c.methods.last().and_then(|m| m.body.last());
+                join(known(&c.name), last_method.and_then(|d| d.span()))
+            }
+            Decl::Statement(s) => s.span(),
         }
     }
 }
@@ -30,9 +160,11 @@ impl Display for Decl {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Decl::Statement(s) => write!(f, "{}", s),
+            Decl::Fun(fun) => write!(f, "{}", fun),
+            Decl::Class(c) => write!(f, "{}", c),
             Decl::VarDecl(t, opt_e) => match opt_e {
-                Some(e) => write!(f, "var {} = {}", t, e),
-                None => write!(f, "var {};", t),
+                Some(e) => write!(f, "var {} = {};", t.s, e),
+                None => write!(f, "var {};", t.s),
             },
         }
     }
@@ -41,19 +173,53 @@ impl Display for Decl {
 /// Statement can be either a print statement or expression statement
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Stmt {
-    Print(Expression),
+    /// print keyword and printed value
+    Print(Token, Expression),
     Expression(Expression),
     Block(Vec<Decl>),
-    Invalid(String),
+    /// if keyword, condition, then branch and optional else branch
+    If(Token, Expression, Box<Stmt>, Option<Box<Stmt>>),
+    /// while (or for) keyword, condition, body and optional label;
+    /// for loops are lowered to while loops by the parser
+    While(Token, Expression, Box<Stmt>, Option<Token>),
+    /// break keyword and optional label of loop to leave
+    Break(Token, Option<Token>),
+    /// continue keyword and optional label of loop to continue
+    Continue(Token, Option<Token>),
+    /// return keyword and optional returned value
+    Return(Token, Option<Expression>),
+    /// placeholder for statement with syntax error (reported in Program::errors)
+    Invalid,
 }
 
 impl Stmt {
-    fn is_valid(&self) -> bool {
+    /// line of statement's keyword or first expression; blocks have none
+    pub(crate) fn line(&self) -> Option<LineNum> {
         match self {
-            Stmt::Print(e) => e.is_valid(),
-            Stmt::Expression(e) => e.is_valid(),
-            Stmt::Block(v) => v.iter().all(|e| e.is_valid()),
-            Stmt::Invalid(_) => false,
+            Stmt::Expression(e) => e.line(),
+            Stmt::Print(k, _) | Stmt::If(k, ..) | Stmt::While(k, ..) => Some(k.ln),
+            Stmt::Break(k, _) | Stmt::Continue(k, _) | Stmt::Return(k, _) => Some(k.ln),
+            Stmt::Block(_) | Stmt::Invalid => None,
+        }
+    }
+
+    /// source range from first to last token stored in this statement
+    pub(crate) fn span(&self) -> Option<Span> {
+        match self {
+            Stmt::Print(k, e) => join(known(k), e.span()),
+            Stmt::Expression(e) => e.span(),
+            Stmt::Block(v) => v.iter().filter_map(|d| d.span()).reduce(Span::to),
+            Stmt::If(k, c, t, e) => {
+                let else_span = e.as_ref().and_then(|e| e.span());
+                join(join(join(known(k), c.span()), t.span()), else_span)
+            }
+            Stmt::While(k, c, b, l) => {
+                let start = join(l.as_ref().and_then(known), known(k));
+                join(join(start, c.span()), b.span())
+            }
+            Stmt::Break(t, l) | Stmt::Continue(t, l) => join(known(t), l.as_ref().and_then(known)),
+            Stmt::Return(t, e) => join(known(t), e.as_ref().and_then(|e| e.span())),
+            Stmt::Invalid => None,
         }
     }
 }
@@ -61,10 +227,28 @@ impl Stmt {
 impl Display for Stmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Stmt::Print(e) => f.write_fmt(format_args!("{}", e)),
+            Stmt::Print(_, e) => f.write_fmt(format_args!("{}", e)),
             Stmt::Expression(e) => f.write_fmt(format_args!("{}", e)),
-            Self::Block(v) => f.write_fmt(format_args!("{:?}", v)),
-            Self::Invalid(s) => f.write_str(s),
+            Self::Block(v) => {
+                f.write_str("(block")?;
+                v.iter().try_for_each(|d| write!(f, " {}", d))?;
+                f.write_str(")")
+            }
+            Self::If(_, c, t, None) => write!(f, "(if {} {})", c, t),
+            Self::If(_, c, t, Some(e)) => write!(f, "(if {} {} {})", c, t, e),
+            Self::While(_, c, b, l) => {
+                if let Some(l) = l {
+                    write!(f, "{}: ", l.s)?;
+                }
+                write!(f, "(while {} {})", c, b)
+            }
+            Self::Break(_, None) => f.write_str("(break)"),
+            Self::Break(_, Some(l)) => write!(f, "(break {})", l.s),
+            Self::Continue(_, None) => f.write_str("(continue)"),
+            Self::Continue(_, Some(l)) => write!(f, "(continue {})", l.s),
+            Self::Return(_, None) => f.write_str("(return)"),
+            Self::Return(_, Some(e)) => write!(f, "(return {})", e),
+            Self::Invalid => f.write_str("(invalid)"),
         }
     }
 }
@@ -73,17 +257,13 @@ impl Display for Stmt {
 #[derive(Debug)]
 pub(crate) struct Program {
     pub declarations: Vec<Decl>,
+    pub errors: Vec<ParseError>,
 }
 
 impl Program {
-    /// returns optional first syntax error
-    pub(crate) fn syntax_errors(&self) -> Option<Decl> {
-        self.declarations
-            .iter()
-            .filter(|s| !s.is_valid())
-            .take(1)
-            .next()
-            .map(Decl::clone)
+    /// syntax errors in source order; declarations containing them hold Invalid placeholders
+    pub(crate) fn syntax_errors(&self) -> &[ParseError] {
+        &self.errors
     }
 }
 impl Display for Program {
@@ -102,7 +282,17 @@ impl Display for Program {
 impl Parser {
     pub(crate) fn new(tokens: Vec<Token>) -> Self {
         //eprint!("new parser: tokens:{:?}", tokens);
-        Parser { tokens, curr: 0 }
+        Parser {
+            tokens,
+            curr: 0,
+            loops: Vec::new(),
+            labels: Vec::new(),
+            functions: 0,
+            blocks: 0,
+            nesting: 0,
+            errors: Vec::new(),
+            panic: false,
+        }
     }
 
     pub(crate) fn parse(&mut self) -> Program {
@@ -112,48 +302,426 @@ impl Parser {
             res.push(self.declaration());
             is_end = self.at_end();
         }
-        Program { declarations: res }
+        Program {
+            declarations: res,
+            errors: std::mem::take(&mut self.errors),
+        }
     }
 
     fn declaration(&mut self) -> Decl {
         let c = self.current();
-        match c.typ {
+        let d = match c.typ {
             TokenType::Var => self.var_declaration(),
+            TokenType::Fun => self.fun_declaration(),
+            TokenType::Class => self.class_declaration(),
             _ => Decl::Statement(self.statement()),
+        };
+        if self.panic {
+            self.synchronize();
+        }
+        d
+    }
+
+    /// Leaves panic mode: skips tokens up to the end of current statement
+    /// (after ';') or to the start of the next one (statement keyword or
+    /// '}' closing the enclosing block, if any)
+    fn synchronize(&mut self) {
+        self.panic = false;
+        while !self.at_end() {
+            match self.current().typ {
+                TokenType::Semicolon => {
+                    self.advance();
+                    return;
+                }
+                TokenType::Class
+                | TokenType::Fun
+                | TokenType::Var
+                | TokenType::For
+                | TokenType::If
+                | TokenType::While
+                | TokenType::Print
+                | TokenType::Return => return,
+                TokenType::RightBrace if self.blocks > 0 => return,
+                _ => self.advance(),
+            }
+        }
+    }
+
+    fn fun_declaration(&mut self) -> Decl {
+        self.advance();
+        match self.function() {
+            Some(f) => Decl::Fun(Rc::new(f)),
+            None => Decl::Statement(Stmt::Invalid),
+        }
+    }
+
+    fn class_declaration(&mut self) -> Decl {
+        self.advance();
+        let name = self.current();
+        if name.typ != TokenType::Identifier {
+            return Decl::Statement(self.invalid_stmt(&name, expect("class name")));
+        }
+        self.advance();
+        let mut superclass = None;
+        if self.current().typ == TokenType::Less {
+            self.advance();
+            if self.current().typ != TokenType::Identifier {
+                let kind = expect("superclass name");
+                return Decl::Statement(self.invalid_stmt(&self.current(), kind));
+            }
+            superclass = Some(Expression::Variable(self.current(), None));
+            self.advance();
+        }
+        if self.current().typ != TokenType::LeftBrace {
+            let kind = expect("'{' before class body");
+            return Decl::Statement(self.invalid_stmt(&self.current(), kind));
+        }
+        self.advance();
+        let mut methods = Vec::new();
+        while self.current().typ != TokenType::RightBrace && !self.at_end() {
+            match self.function() {
+                Some(m) => methods.push(Rc::new(m)),
+                None => return Decl::Statement(Stmt::Invalid),
+            }
+        }
+        if self.current().typ != TokenType::RightBrace {
+            let kind = expect("'}' after class body");
+            return Decl::Statement(self.invalid_stmt(&self.current(), kind));
+        }
+        self.advance();
+        Decl::Class(Rc::new(Class {
+            name,
+            superclass,
+            methods,
+        }))
+    }
+
+    /// Parses function name, parameters and body.
+    /// Loops enclosing the declaration are not visible to break/continue in its body.
+    fn function(&mut self) -> Option<Function> {
+        let name = self.current();
+        if name.typ != TokenType::Identifier {
+            self.report(&name, expect("function name"));
+            return None;
+        }
+        self.advance();
+        if self.current().typ != TokenType::LeftParen {
+            self.report(&self.current(), expect("'(' after function name"));
+            return None;
+        }
+        self.advance();
+        let mut params = Vec::new();
+        if self.current().typ != TokenType::RightParen {
+            loop {
+                if params.len() == MAX_ARGS {
+                    self.report(&self.current(), ParseErrorKind::TooManyParameters);
+                }
+                if self.current().typ != TokenType::Identifier {
+                    self.report(&self.current(), expect("parameter name"));
+                    return None;
+                }
+                params.push(self.current());
+                self.advance();
+                if self.current().typ != TokenType::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        if self.current().typ != TokenType::RightParen {
+            self.report(&self.current(), expect("')' after parameters"));
+            return None;
+        }
+        self.advance();
+        if self.current().typ != TokenType::LeftBrace {
+            self.report(&self.current(), expect("'{' before function body"));
+            return None;
+        }
+
+        let loops = std::mem::take(&mut self.loops);
+        self.functions += 1;
+        let body = self.nested(Self::block, Stmt::Invalid);
+        self.functions -= 1;
+        self.loops = loops;
+
+        match body {
+            Stmt::Block(body) => Some(Function { name, params, body }),
+            Stmt::Invalid => None,
+            _ => unreachable!("block() returns block or invalid statement"),
         }
     }
 
     fn statement(&mut self) -> Stmt {
+        self.nested(Self::unnested_statement, Stmt::Invalid)
+    }
+
+    fn unnested_statement(&mut self) -> Stmt {
         let c = self.current();
 
         match c.typ {
             TokenType::Print => self.print_statement(),
             TokenType::LeftBrace => self.block(),
+            TokenType::If => self.if_statement(),
+            TokenType::While => self.while_statement(None),
+            TokenType::For => self.for_statement(None),
+            TokenType::Break | TokenType::Continue => self.loop_control_statement(),
+            TokenType::Return => self.return_statement(),
+            TokenType::Identifier if self.peek_next().typ == TokenType::Colon => {
+                self.labeled_statement()
+            }
             _ => self.expression_statement(),
         }
     }
 
     fn print_statement(&mut self) -> Stmt {
+        let keyword = self.current();
         self.advance();
-        let s = Stmt::Print(self.expression());
+        let s = Stmt::Print(keyword, self.expression());
         if self.current().typ == TokenType::Semicolon {
             self.advance();
         };
         s
     }
 
+    /// Parses if statement. Else branch binds to the nearest if,
+    /// which resolves the dangling else ambiguity.
+    fn if_statement(&mut self) -> Stmt {
+        let keyword = self.current();
+        self.advance();
+        if self.current().typ != TokenType::LeftParen {
+            return self.invalid_stmt(&self.current(), expect("'(' after 'if'"));
+        }
+        self.advance();
+        let condition = self.expression();
+        if self.current().typ != TokenType::RightParen {
+            return self.invalid_stmt(&self.current(), expect("')' after if condition"));
+        }
+        self.advance();
+        let then_branch = self.statement();
+        let else_branch = if self.current().typ == TokenType::Else {
+            self.advance();
+            Some(Box::new(self.statement()))
+        } else {
+            None
+        };
+        Stmt::If(keyword, condition, Box::new(then_branch), else_branch)
+    }
+
+    /// Parses `label: while ...` or `label: for ...`
+    fn labeled_statement(&mut self) -> Stmt {
+        let label = self.current();
+        self.labels.push(label.s.clone());
+        self.advance();
+        self.advance();
+        match self.current().typ {
+            TokenType::While => self.while_statement(Some(label)),
+            TokenType::For => self.for_statement(Some(label)),
+            _ => self.invalid_stmt(&self.current(), expect("loop after label")),
+        }
+    }
+
+    /// Parses body of a loop labeled with optional label
+    fn loop_body(&mut self, label: &Option<Token>) -> Stmt {
+        self.loops.push(label.as_ref().map(|l| l.s.clone()));
+        let body = self.statement();
+        self.loops.pop();
+        body
+    }
+
+    /// Parses break or continue with optional label.
+    /// Both are only allowed inside a loop (with matching label).
+    fn loop_control_statement(&mut self) -> Stmt {
+        let keyword = self.current();
+        self.advance();
+        let label = if self.current().typ == TokenType::Identifier {
+            let l = self.current();
+            self.advance();
+            Some(l)
+        } else {
+            None
+        };
+        if self.current().typ != TokenType::Semicolon {
+            let kind = ParseErrorKind::Expect(format!("';' after '{}'", keyword.s));
+            return self.invalid_stmt(&self.current(), kind);
+        }
+        self.advance();
+
+        if self.loops.is_empty() {
+            let kind = ParseErrorKind::LoopControlOutsideLoop(keyword.s.clone());
+            return self.invalid_stmt(&keyword, kind);
+        }
+        if let Some(l) = &label {
+            if !self.loops.contains(&Some(l.s.clone())) {
+                return self.invalid_stmt(l, ParseErrorKind::UndefinedLabel(l.s.clone()));
+            }
+        }
+        match keyword.typ {
+            TokenType::Break => Stmt::Break(keyword, label),
+            _ => Stmt::Continue(keyword, label),
+        }
+    }
+
+    fn return_statement(&mut self) -> Stmt {
+        let keyword = self.current();
+        self.advance();
+        let value = if self.current().typ != TokenType::Semicolon {
+            Some(self.expression())
+        } else {
+            None
+        };
+        if self.current().typ != TokenType::Semicolon {
+            return self.invalid_stmt(&self.current(), expect("';' after return value"));
+        }
+        self.advance();
+        if self.functions == 0 {
+            return self.invalid_stmt(&keyword, ParseErrorKind::TopLevelReturn);
+        }
+        Stmt::Return(keyword, value)
+    }
+
+    fn while_statement(&mut self, label: Option<Token>) -> Stmt {
+        let keyword = self.current();
+        self.advance();
+        if self.current().typ != TokenType::LeftParen {
+            return self.invalid_stmt(&self.current(), expect("'(' after 'while'"));
+        }
+        self.advance();
+        let condition = self.expression();
+        if self.current().typ != TokenType::RightParen {
+            return self.invalid_stmt(&self.current(), expect("')' after condition"));
+        }
+        self.advance();
+        let body = self.loop_body(&label);
+        Stmt::While(keyword, condition, Box::new(body), label)
+    }
+
+    /// Parses for statement and lowers it to while loop:
+    /// `for (init; cond; incr) body` becomes
+    /// `{ init; while (cond) { body; incr; } }`.
+    /// If body continues the for loop, it is wrapped in a loop run once:
+    /// `{ init; for_1: while (cond) { for_1_body: while (true) { body; break for_1_body; }
+    /// incr; } }`, with `continue` turned into `break for_1_body` and unlabeled `break`
+    /// into `break for_1`.
+    fn for_statement(&mut self, label: Option<Token>) -> Stmt {
+        let keyword = self.current();
+        self.advance();
+        if self.current().typ != TokenType::LeftParen {
+            return self.invalid_stmt(&self.current(), expect("'(' after 'for'"));
+        }
+        self.advance();
+
+        let initializer = match self.current().typ {
+            TokenType::Semicolon => {
+                self.advance();
+                None
+            }
+            TokenType::Var => Some(self.var_declaration()),
+            _ => {
+                let e = self.expression();
+                if self.current().typ != TokenType::Semicolon {
+                    return self.invalid_stmt(&self.current(), expect("';' after expression"));
+                }
+                self.advance();
+                Some(Decl::Statement(Stmt::Expression(e)))
+            }
+        };
+
+        let condition = if self.current().typ != TokenType::Semicolon {
+            self.expression()
+        } else {
+            Expression::Primary(Token::new(TokenType::True, self.current().ln, "true".into()))
+        };
+        if self.current().typ != TokenType::Semicolon {
+            return self.invalid_stmt(&self.current(), expect("';' after loop condition"));
+        }
+        self.advance();
+
+        let increment = if self.current().typ != TokenType::RightParen {
+            Some(self.expression())
+        } else {
+            None
+        };
+        if self.current().typ != TokenType::RightParen {
+            return self.invalid_stmt(&self.current(), expect("')' after for clauses"));
+        }
+        self.advance();
+
+        let body = self.loop_body(&label);
+        let (body, label) = self.continue_into_body_end(&keyword, body, label);
+        let body = match increment {
+            Some(i) => Stmt::Block(vec![
+                Decl::Statement(body),
+                Decl::Statement(Stmt::Expression(i)),
+            ]),
+            None => body,
+        };
+        let while_loop = Stmt::While(keyword, condition, Box::new(body), label);
+        match initializer {
+            Some(init) => Stmt::Block(vec![init, Decl::Statement(while_loop)]),
+            None => while_loop,
+        }
+    }
+
+    /// Wraps body of for loop continued by its `continue` statements in a loop run once, so
+    /// that the increment runs after them. Returns body and label of the lowered for loop.
+    fn continue_into_body_end(
+        &mut self,
+        keyword: &Token,
+        mut body: Stmt,
+        label: Option<Token>,
+    ) -> (Stmt, Option<Token>) {
+        let name = label.as_ref().map(|l| l.s.clone());
+        let mut continued = false;
+        retarget(&mut body, name.as_deref(), false, &mut |s| {
+            continued |= matches!(s, Stmt::Continue(..));
+        });
+        if !continued {
+            return (body, label);
+        }
+
+        let n = (1..)
+            .find(|n| {
+                let taken = |l: String| self.labels.contains(&l);
+                !taken(format!("for_{}", n)) && !taken(format!("for_{}_body", n))
+            })
+            .unwrap();
+        let made_up = |s: String| Token::new(TokenType::Identifier, keyword.ln, s);
+        let label = label.unwrap_or_else(|| made_up(format!("for_{}", n)));
+        let body_label = made_up(format!("for_{}_body", n));
+        self.labels.push(label.s.clone());
+        self.labels.push(body_label.s.clone());
+
+        retarget(&mut body, name.as_deref(), false, &mut |s| {
+            *s = match s {
+                Stmt::Continue(k, _) => Stmt::Break(k.clone(), Some(body_label.clone())),
+                Stmt::Break(k, _) => Stmt::Break(k.clone(), Some(label.clone())),
+                _ => return,
+            }
+        });
+        let end = Token::new(TokenType::Break, keyword.ln, "break".into());
+        let once = Stmt::Block(vec![
+            Decl::Statement(body),
+            Decl::Statement(Stmt::Break(end, Some(body_label.clone()))),
+        ]);
+        let always = Expression::Primary(Token::new(TokenType::True, keyword.ln, "true".into()));
+        let body = Stmt::While(keyword.clone(), always, Box::new(once), Some(body_label));
+        (body, Some(label))
+    }
+
     fn block(&mut self) -> Stmt {
         self.advance();
         let mut statements: Vec<Decl> = Vec::new();
+        self.blocks += 1;
         while (self.current().typ != TokenType::RightBrace) && (!self.at_end()) {
             let d = self.declaration();
             statements.push(d);
         }
+        self.blocks -= 1;
         if self.current().typ == TokenType::RightBrace {
             self.advance();
             Stmt::Block(statements)
         } else {
-            Stmt::Invalid(format!("[line {}] Error at end: Expect '}}'", self.current().ln))
+            self.invalid_stmt(&self.current(), expect("'}' after block"))
         }
     }
 
@@ -179,8 +747,89 @@ impl Parser {
         self.tokens.get(self.curr).unwrap().clone()
     }
 
+    /// token after current one (or the last one when current is the last)
+    fn peek_next(&self) -> token::Token {
+        self.tokens
+            .get(self.curr + 1)
+            .unwrap_or_else(|| self.tokens.last().unwrap())
+            .clone()
+    }
+
+    /// records syntax error of given kind at token t unless in panic mode.
+    /// Missing tokens leave the parser in the middle of a construct,
+    /// so they start panic mode.
+    fn report(&mut self, t: &Token, kind: ParseErrorKind) {
+        if self.panic {
+            return;
+        }
+        self.panic = matches!(
+            kind,
+            ParseErrorKind::Expect(_)
+                | ParseErrorKind::ExpectExpression
+                | ParseErrorKind::TooMuchNesting
+        );
+        let e = ParseError::at(t, kind);
+        let e = match &e.kind {
+            ParseErrorKind::InvalidAssignmentTarget => {
+                e.with_note("only variables and properties can be assigned to")
+            }
+            ParseErrorKind::Expect(what) if what.starts_with("';'") => {
+                e.with_note("statements must end with ';'")
+            }
+            _ => e,
+        };
+        self.errors.push(e);
+    }
+
+    /// records syntax error and returns statement placeholder
+    fn invalid_stmt(&mut self, t: &Token, kind: ParseErrorKind) -> Stmt {
+        self.report(t, kind);
+        Stmt::Invalid
+    }
+
+    /// records syntax error and returns expression placeholder
+    fn invalid_expr(&mut self, t: &Token, kind: ParseErrorKind) -> Expression {
+        self.report(t, kind);
+        Expression::Invalid
+    }
+
     fn expression(&mut self) -> Expression {
-        self.assignment()
+        self.nested(Self::assignment, Expression::Invalid)
+    }
+
+    /// Parses with parse one level deeper; too deep nesting is reported at current token
+    /// and gives invalid placeholder
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> T, invalid: T) -> T {
+        if self.nesting >= MAX_NESTING {
+            self.report(&self.current(), ParseErrorKind::TooMuchNesting);
+            return invalid;
+        }
+        self.nesting += 1;
+        let res = parse(self);
+        self.nesting -= 1;
+        res
+    }
+
+    fn or(&mut self) -> Expression {
+        let mut expr = self.and();
+        while self.current().typ == TokenType::Or {
+            let operator = self.current();
+            self.advance();
+            let right = Box::new(self.and());
+            expr = Expression::LogicalEx(Box::new(expr), Logical::Or, operator, right);
+        }
+        expr
+    }
+
+    fn and(&mut self) -> Expression {
+        let mut expr = self.equality();
+        while self.current().typ == TokenType::And {
+            let operator = self.current();
+            self.advance();
+            let right = Box::new(self.equality());
+            expr = Expression::LogicalEx(Box::new(expr), Logical::And, operator, right);
+        }
+        expr
     }
 
     fn equality(&mut self) -> crate::parser::Expression {
@@ -193,6 +842,7 @@ impl Parser {
                     expr = Expression::BinaryEx(
                         Box::new(expr),
                         Binary::new(&curr_token),
+                        curr_token,
                         Box::new(self.comparison()),
                     );
                 }
@@ -216,6 +866,7 @@ impl Parser {
                     expr = Expression::BinaryEx(
                         Box::new(expr),
                         Binary::new(&curr_token),
+                        curr_token,
                         Box::new(self.term()),
                     );
                 }
@@ -235,6 +886,7 @@ impl Parser {
                     factor = Expression::BinaryEx(
                         Box::new(factor),
                         Binary::new(&curr_token),
+                        curr_token,
                         Box::new(self.factor()),
                     );
                 }
@@ -254,6 +906,7 @@ impl Parser {
                     expr = Expression::BinaryEx(
                         Box::new(expr),
                         Binary::new(&curr_token),
+                        curr_token,
                         Box::new(self.unary()),
                     )
                 }
@@ -268,12 +921,61 @@ impl Parser {
         match curr_token.typ {
             TokenType::Bang | TokenType::Minus => {
                 self.advance();
-                Expression::UnaryEx(Unary::new(&curr_token), Box::new(self.unary()))
+                let operand = self.nested(Self::unary, Expression::Invalid);
+                Expression::UnaryEx(Unary::new(&curr_token), curr_token, Box::new(operand))
             }
-            _ => self.primary(),
+            _ => self.call(),
         }
     }
 
+    fn call(&mut self) -> Expression {
+        let mut expr = self.primary();
+        loop {
+            match self.current().typ {
+                TokenType::LeftParen => {
+                    self.advance();
+                    expr = self.finish_call(expr);
+                }
+                TokenType::Dot => {
+                    self.advance();
+                    let name = self.current();
+                    if name.typ != TokenType::Identifier {
+                        return self.invalid_expr(&name, expect("property name after '.'"));
+                    }
+                    self.advance();
+                    expr = Expression::Get(Box::new(expr), name);
+                }
+                _ => break,
+            }
+        }
+        expr
+    }
+
+    /// Parses arguments of a call to callee; current token is after '('
+    fn finish_call(&mut self, callee: Expression) -> Expression {
+        let mut args = Vec::new();
+        if self.current().typ != TokenType::RightParen {
+            loop {
+                if args.len() == MAX_ARGS {
+                    self.report(&self.current(), ParseErrorKind::TooManyArguments);
+                }
+                args.push(self.expression());
+                if self.current().typ != TokenType::Comma {
+                    break;
+                }
+                self.advance();
+            }
+        }
+        let paren = self.current();
+        if paren.typ != TokenType::RightParen {
+            return self.invalid_expr(&paren, expect("')' after arguments"));
+        }
+        self.advance();
+        Expression::Call(Box::new(callee), paren, args)
+    }
+
+    /// Parses literal, variable, grouping or `super` access; on syntax error the
+    /// offending token is left to be skipped by synchronize
     fn primary(&mut self) -> Expression {
         let curr = self.current();
         let prim = match curr.typ {
@@ -285,29 +987,40 @@ impl Parser {
             TokenType::LeftParen => {
                 self.advance();
                 let e = self.expression();
-                match self.current().typ {
-                    TokenType::RightParen => Expression::Paren(Box::new(e)),
-                    _other => Expression::Invalid(
-                        format!("[line {} Error at {}: Expected right paren", curr.ln, curr.s).to_owned(),
-                    ),
+                let close = self.current();
+                if close.typ != TokenType::RightParen {
+                    return self.invalid_expr(&close, expect("')' after expression"));
                 }
+                Expression::Paren(curr, Box::new(e), close)
             }
-            TokenType::Identifier => Expression::Variable(curr),
-            _other => Expression::Invalid(
-                format!("[line {}] Error at {}: Expected primary (number,  string, bool, nil)  or left paren", curr.ln, curr.s),
-            ),
+            TokenType::Identifier => Expression::Variable(curr, None),
+            TokenType::This => Expression::This(curr, None),
+            TokenType::Super => return self.super_method(curr),
+            _ => return self.invalid_expr(&curr, ParseErrorKind::ExpectExpression),
         };
         self.advance();
         prim
     }
 
+    /// Parses `super.method`
+    fn super_method(&mut self, keyword: Token) -> Expression {
+        self.advance();
+        if self.current().typ != TokenType::Dot {
+            return self.invalid_expr(&self.current(), expect("'.' after 'super'"));
+        }
+        self.advance();
+        if self.current().typ != TokenType::Identifier {
+            return self.invalid_expr(&self.current(), expect("superclass method name"));
+        }
+        let method = self.current();
+        self.advance();
+        Expression::Super(keyword, method, None)
+    }
+
     fn var_declaration(&mut self) -> Decl {
         self.advance();
         if self.current().typ != TokenType::Identifier {
-            Decl::VarDecl(
-                self.current(),
-                Some(Expression::Invalid("Expect variable name.".to_string())),
-            )
+            Decl::Statement(self.invalid_stmt(&self.current(), expect("variable name")))
         } else {
             let ident_token = self.current().clone();
             self.advance();
@@ -320,12 +1033,8 @@ impl Parser {
             };
 
             if self.current().typ != TokenType::Semicolon {
-                Decl::VarDecl(
-                    self.current(),
-                    Some(Expression::Invalid(
-                        "Expect ';' after variable declaration.".to_string(),
-                    )),
-                )
+                let kind = expect("';' after variable declaration");
+                Decl::Statement(self.invalid_stmt(&self.current(), kind))
             } else {
                 self.advance();
                 Decl::VarDecl(ident_token, initializer)
@@ -334,15 +1043,16 @@ impl Parser {
     }
 
     fn assignment(&mut self) -> Expression {
-        let expr = self.equality();
-        //let equals: Token = self.current().clone();
+        let expr = self.or();
+        let equals = self.current();
         // see this trick here: https://craftinginterpreters.com/statements-and-state.html#assignment
-        if self.current().typ == TokenType::Equal {
+        if equals.typ == TokenType::Equal {
             self.advance();
-            let value = self.assignment();
+            let value = self.expression();
             match expr {
-                Expression::Variable(tok) => Expression::Assign(tok.clone(), Box::new(value)),
-                _ => Expression::Invalid("Invalid assignment target.".to_string()),
+                Expression::Variable(tok, _) => Expression::Assign(tok, Box::new(value), None),
+                Expression::Get(object, name) => Expression::Set(object, name, Box::new(value)),
+                _ => self.invalid_expr(&equals, ParseErrorKind::InvalidAssignmentTarget),
             }
         } else {
             expr
@@ -402,6 +1112,22 @@ impl Binary {
     }
 }
 
+/// Short-circuiting logical operators
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Logical {
+    And,
+    Or,
+}
+
+impl Display for Logical {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Logical::And => f.write_str("and"),
+            Logical::Or => f.write_str("or"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Unary {
     Minus,
@@ -433,16 +1159,60 @@ impl Unary {
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) enum Expression {
     Primary(Token),
-    BinaryEx(Box<Expression>, Binary, Box<Expression>),
-    UnaryEx(Unary, Box<Expression>),
-    Paren(Box<Expression>),
-    Variable(Token),
-    Assign(Token, Box<Expression>),
-    Invalid(String),
+    /// left operand, operator, its token and right operand
+    BinaryEx(Box<Expression>, Binary, Token, Box<Expression>),
+    /// as BinaryEx; right operand is evaluated only when left one does not decide the result
+    LogicalEx(Box<Expression>, Logical, Token, Box<Expression>),
+    /// callee, closing paren (for error reporting) and arguments
+    Call(Box<Expression>, Token, Vec<Expression>),
+    /// property access: object and property name
+    Get(Box<Expression>, Token),
+    /// property assignment: object, property name and value
+    Set(Box<Expression>, Token, Box<Expression>),
+    /// `this` keyword and scope distance as in Variable
+    This(Token, Option<usize>),
+    /// super keyword, method name and resolved scope distance of `super`
+    Super(Token, Token, Option<usize>),
+    /// operator, its token and operand
+    UnaryEx(Unary, Token, Box<Expression>),
+    /// opening paren, grouped expression and closing paren
+    Paren(Token, Box<Expression>, Token),
+    /// variable and number of scopes between its use and declaration
+    /// (computed by resolver, None for globals)
+    Variable(Token, Option<usize>),
+    /// assignment target, value and scope distance as in Variable
+    Assign(Token, Box<Expression>, Option<usize>),
+    /// placeholder for expression with syntax error (reported in Program::errors)
+    Invalid,
 }
 impl Expression {
-    fn is_valid(&self) -> bool {
-        !matches!(self, Self::Invalid(_))
+    /// line of first token stored in this expression (where its span starts)
+    pub(crate) fn line(&self) -> Option<LineNum> {
+        match self {
+            Self::Primary(t) | Self::Variable(t, _) | Self::This(t, _) => Some(t.ln),
+            Self::Assign(t, ..) | Self::Super(t, ..) => Some(t.ln),
+            Self::BinaryEx(l, ..) | Self::LogicalEx(l, ..) => l.line(),
+            Self::UnaryEx(_, t, _) | Self::Paren(t, ..) => Some(t.ln),
+            Self::Call(c, ..) => c.line(),
+            Self::Get(o, _) | Self::Set(o, ..) => o.line(),
+            Self::Invalid => None,
+        }
+    }
+
+    /// source range from first to last token stored in this expression
+    pub(crate) fn span(&self) -> Option<Span> {
+        match self {
+            Self::Primary(t) | Self::Variable(t, _) | Self::This(t, _) => known(t),
+            Self::BinaryEx(l, _, _, r) | Self::LogicalEx(l, _, _, r) => join(l.span(), r.span()),
+            Self::UnaryEx(_, op, e) => join(known(op), e.span()),
+            Self::Paren(open, _, close) => join(known(open), known(close)),
+            Self::Assign(t, e, _) => join(known(t), e.span()),
+            Self::Call(c, paren, _) => join(c.span(), known(paren)),
+            Self::Get(o, name) => join(o.span(), known(name)),
+            Self::Set(o, _, v) => join(o.span(), v.span()),
+            Self::Super(keyword, method, _) => join(known(keyword), known(method)),
+            Self::Invalid => None,
+        }
     }
 }
 
@@ -457,12 +1227,22 @@ impl Display for Expression {
                 TokenType::StringLiteral => f.write_str(&t.s),
                 other => f.write_str(&other.to_string()),
             },
-            Self::BinaryEx(l, o, r) => f.write_fmt(format_args!("({} {} {})", o, l, r)),
-            Self::UnaryEx(o, e) => f.write_fmt(format_args!("({} {})", o, e)),
-            Self::Paren(e) => f.write_fmt(format_args!("(group {})", e)),
-            Self::Variable(e) => f.write_fmt(format_args!("(var {})", e)),
-            Self::Assign(t, e) => write!(f, "({} = {})", t, e),
-            Self::Invalid(s) => f.write_fmt(format_args!("Parse error: {}", s)),
+            Self::BinaryEx(l, o, _, r) => f.write_fmt(format_args!("({} {} {})", o, l, r)),
+            Self::LogicalEx(l, o, _, r) => write!(f, "({} {} {})", o, l, r),
+            Self::Get(o, name) => write!(f, "(get {} {})", o, name.s),
+            Self::Set(o, name, v) => write!(f, "(set {} {} {})", o, name.s, v),
+            Self::This(..) => f.write_str("this"),
+            Self::Super(_, method, _) => write!(f, "(super {})", method.s),
+            Self::Call(c, _, args) => {
+                write!(f, "(call {}", c)?;
+                args.iter().try_for_each(|a| write!(f, " {}", a))?;
+                f.write_str(")")
+            }
+            Self::UnaryEx(o, _, e) => f.write_fmt(format_args!("({} {})", o, e)),
+            Self::Paren(_, e, _) => f.write_fmt(format_args!("(group {})", e)),
+            Self::Variable(e, _) => f.write_fmt(format_args!("(var {})", e)),
+            Self::Assign(t, e, _) => write!(f, "({} = {})", t, e),
+            Self::Invalid => f.write_str("(invalid)"),
         }
     }
 }
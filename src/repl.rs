@@ -0,0 +1,110 @@
+/// Interactive read-eval-print loop. One Interpreter runs all inputs, so variables,
+/// functions and classes defined by earlier inputs stay available, also after errors.
+use std::{
+    cell::RefCell,
+    io::{self, BufRead, Write},
+    rc::Rc,
+};
+
+use crate::{
+    evaluator::StatementEvalResult,
+    interpreter::Interpreter,
+    lexer::Lexer,
+    parser::{Decl, Parser, Stmt},
+    token::{LexicalError, TokenType},
+};
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+
+/// Writer shared by the loop (prompts, values) and the interpreter (output, errors)
+struct Shared<W>(Rc<RefCell<W>>);
+
+impl<W> Clone for Shared<W> {
+    fn clone(&self) -> Self {
+        Shared(self.0.clone())
+    }
+}
+
+impl<W: Write> Write for Shared<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.borrow_mut().flush()
+    }
+}
+
+/// input ends inside parentheses, braces, string or block comment,
+/// so it continues on next line
+pub(crate) fn is_incomplete(source: &str) -> bool {
+    let mut depth = 0i64;
+    for t in Lexer::new(source) {
+        match t.typ {
+            TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+            TokenType::Unknown(
+                LexicalError::UnterminatedString | LexicalError::UnterminatedBlockComment,
+            ) => return true,
+            _ => (),
+        }
+    }
+    depth > 0
+}
+
+/// for each top level declaration of source, whether it is an expression statement
+fn bare_expressions(source: &str) -> Vec<bool> {
+    Parser::new(Lexer::new(source).tokens())
+        .parse()
+        .declarations
+        .iter()
+        .map(|d| matches!(d, Decl::Statement(Stmt::Expression(_))))
+        .collect()
+}
+
+/// Reads inputs until end of input or call of `exit`, returning exit code;
+/// values of expression statements are printed.
+/// Statement on last line may omit its semicolon
+pub(crate) fn run<R: BufRead, W: Write, E: Write>(input: R, out: W, err: E) -> io::Result<u8> {
+    let out = Shared(Rc::new(RefCell::new(out)));
+    let err = Shared(Rc::new(RefCell::new(err)));
+    let mut interpreter = Interpreter::with_writers(out.clone(), err.clone());
+    let (mut out, mut err) = (out, err);
+    let mut lines = input.lines();
+    let mut source = String::new();
+    loop {
+        let prompt = if source.is_empty() { PROMPT } else { CONTINUATION_PROMPT };
+        write!(out, "{}", prompt)?;
+        out.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            writeln!(out)?;
+            return Ok(0);
+        };
+        source.push_str(&line);
+        source.push('\n');
+        if is_incomplete(&source) {
+            continue;
+        }
+        let mut input = std::mem::take(&mut source);
+        match Lexer::new(&input).filter(|t| t.typ != TokenType::Eof).last() {
+            None => continue,
+            Some(t) if matches!(t.typ, TokenType::Semicolon | TokenType::RightBrace) => (),
+            // right after the last token, so errors at it are reported on its line
+            Some(t) => input.insert(t.span.end, ';'),
+        }
+        match interpreter.run_source(&input) {
+            Ok(results) => {
+                for (r, bare) in results.iter().zip(bare_expressions(&input)) {
+                    if let (StatementEvalResult::ExpressionStatementResult(v), true) = (r, bare) {
+                        writeln!(out, "{}", v)?;
+                    }
+                }
+                if let Some(code) = interpreter.exit_status() {
+                    return Ok(code);
+                }
+            }
+            Err(_) => writeln!(err)?,
+        }
+    }
+}
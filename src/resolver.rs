@@ -0,0 +1,231 @@
+/// Static analysis pass run between parsing and evaluation.
+/// Resolves every use of a local variable to the number of scopes between
+/// the use and the declaration (see Expression::Variable) and reports static errors.
+/// See https://craftinginterpreters.com/resolving-and-binding.html
+use std::{collections::HashMap, rc::Rc};
+
+use crate::{
+    diagnostics::{ParseError, ParseErrorKind},
+    parser::{Class, Decl, Expression, Function, Program, Stmt},
+    token::Token,
+};
+
+/// Kind of function being resolved
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
+/// Kind of class being resolved
+#[derive(Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+pub(crate) struct Resolver {
+    /// stack of local scopes; value tells if variable's initializer is already resolved.
+    /// Globals are not tracked.
+    scopes: Vec<HashMap<String, bool>>,
+    errors: Vec<ParseError>,
+    current_function: FunctionType,
+    current_class: ClassType,
+}
+
+impl Resolver {
+    pub(crate) fn new() -> Self {
+        Resolver {
+            scopes: Vec::new(),
+            errors: Vec::new(),
+            current_function: FunctionType::None,
+            current_class: ClassType::None,
+        }
+    }
+
+    /// resolves variables in program; returns static errors in source order
+    pub(crate) fn resolve(mut self, p: &mut Program) -> Vec<ParseError> {
+        for d in p.declarations.iter_mut() {
+            self.resolve_decl(d);
+        }
+        self.errors
+    }
+
+    fn resolve_decl(&mut self, d: &mut Decl) {
+        match d {
+            Decl::VarDecl(t, init) => {
+                self.declare(t);
+                if let Some(e) = init {
+                    self.resolve_expr(e);
+                }
+                self.define(t);
+            }
+            Decl::Fun(f) => {
+                self.declare(&f.name);
+                self.define(&f.name);
+                self.resolve_function(Rc::make_mut(f), FunctionType::Function);
+            }
+            Decl::Class(c) => self.resolve_class(Rc::make_mut(c)),
+            Decl::Statement(s) => self.resolve_stmt(s),
+        }
+    }
+
+    /// methods are resolved in a scope defining `this`,
+    /// enclosed by a scope defining `super` for subclasses
+    fn resolve_class(&mut self, c: &mut Class) {
+        let enclosing_class = self.current_class;
+        self.current_class = ClassType::Class;
+        self.declare(&c.name);
+        self.define(&c.name);
+
+        if let Some(sc) = c.superclass.as_mut() {
+            if let Expression::Variable(t, _) = sc {
+                if t.s == c.name.s {
+                    self.error(t, ParseErrorKind::InheritsFromItself);
+                }
+            }
+            self.current_class = ClassType::Subclass;
+            self.resolve_expr(sc);
+            self.scopes.push(HashMap::from([("super".to_string(), true)]));
+        }
+
+        self.scopes.push(HashMap::from([("this".to_string(), true)]));
+        for m in c.methods.iter_mut() {
+            let typ = if m.name.s == "init" {
+                FunctionType::Initializer
+            } else {
+                FunctionType::Method
+            };
+            self.resolve_function(Rc::make_mut(m), typ);
+        }
+        self.scopes.pop();
+        if c.superclass.is_some() {
+            self.scopes.pop();
+        }
+        self.current_class = enclosing_class;
+    }
+
+    fn resolve_function(&mut self, f: &mut Function, typ: FunctionType) {
+        let enclosing_function = self.current_function;
+        self.current_function = typ;
+        self.scopes.push(HashMap::new());
+        for p in f.params.iter() {
+            self.declare(p);
+            self.define(p);
+        }
+        for d in f.body.iter_mut() {
+            self.resolve_decl(d);
+        }
+        self.scopes.pop();
+        self.current_function = enclosing_function;
+    }
+
+    fn resolve_stmt(&mut self, s: &mut Stmt) {
+        match s {
+            Stmt::Print(_, e) | Stmt::Expression(e) => self.resolve_expr(e),
+            Stmt::Block(v) => {
+                self.scopes.push(HashMap::new());
+                for d in v.iter_mut() {
+                    self.resolve_decl(d);
+                }
+                self.scopes.pop();
+            }
+            Stmt::If(_, c, t, e) => {
+                self.resolve_expr(c);
+                self.resolve_stmt(t);
+                if let Some(e) = e {
+                    self.resolve_stmt(e);
+                }
+            }
+            Stmt::While(_, c, b, _) => {
+                self.resolve_expr(c);
+                self.resolve_stmt(b);
+            }
+            Stmt::Return(keyword, Some(e)) => {
+                if self.current_function == FunctionType::Initializer {
+                    self.error(keyword, ParseErrorKind::InitializerReturnsValue);
+                }
+                self.resolve_expr(e);
+            }
+            Stmt::Return(_, None) | Stmt::Break(..) | Stmt::Continue(..) | Stmt::Invalid => (),
+        }
+    }
+
+    fn resolve_expr(&mut self, e: &mut Expression) {
+        match e {
+            Expression::Variable(t, depth) => {
+                if let Some(false) = self.scopes.last().and_then(|s| s.get(&t.s)) {
+                    self.error(t, ParseErrorKind::ReadInOwnInitializer);
+                }
+                *depth = self.resolve_local(t);
+            }
+            Expression::Assign(t, value, depth) => {
+                self.resolve_expr(value);
+                *depth = self.resolve_local(t);
+            }
+            Expression::BinaryEx(l, .., r) | Expression::LogicalEx(l, .., r) => {
+                self.resolve_expr(l);
+                self.resolve_expr(r);
+            }
+            Expression::UnaryEx(_, _, e) | Expression::Paren(_, e, _) | Expression::Get(e, _) => {
+                self.resolve_expr(e)
+            }
+            Expression::Set(object, _, value) => {
+                self.resolve_expr(value);
+                self.resolve_expr(object);
+            }
+            Expression::This(t, depth) => {
+                if self.current_class == ClassType::None {
+                    self.error(t, ParseErrorKind::ThisOutsideClass);
+                }
+                *depth = self.resolve_local(t);
+            }
+            Expression::Super(t, _, depth) => {
+                match self.current_class {
+                    ClassType::None => self.error(t, ParseErrorKind::SuperOutsideClass),
+                    ClassType::Class => self.error(t, ParseErrorKind::SuperWithoutSuperclass),
+                    ClassType::Subclass => (),
+                }
+                *depth = self.resolve_local(t);
+            }
+            Expression::Call(c, _, args) => {
+                self.resolve_expr(c);
+                for a in args.iter_mut() {
+                    self.resolve_expr(a);
+                }
+            }
+            Expression::Primary(_) | Expression::Invalid => (),
+        }
+    }
+
+    /// number of scopes between innermost one and the one declaring t (None for globals)
+    fn resolve_local(&self, t: &Token) -> Option<usize> {
+        self.scopes
+            .iter()
+            .rev()
+            .position(|scope| scope.contains_key(&t.s))
+    }
+
+    fn declare(&mut self, t: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&t.s) {
+                self.error(t, ParseErrorKind::AlreadyDeclared);
+            } else {
+                scope.insert(t.s.clone(), false);
+            }
+        }
+    }
+
+    fn define(&mut self, t: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(t.s.clone(), true);
+        }
+    }
+
+    fn error(&mut self, t: &Token, kind: ParseErrorKind) {
+        self.errors.push(ParseError::at(t, kind));
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod ast_dump_tests {
+    use crate::ast_dump::program;
+    use crate::cli::ast_json;
+    use crate::interpreter::check;
+
+    #[test]
+    fn tree_shows_precedence_and_positions() {
+        let tree = program(&check("print 1 + 2 * -x == (3);\nf(a, \"s\");").unwrap());
+        assert_eq!(
+            tree.to_text(),
+            "Program\n\
+             └── declarations\n    \
+                 ├── Print [1:1]\n    \
+                 │   └── expression: Binary == [1:18]\n    \
+                 │       ├── left: Binary + [1:9]\n    \
+                 │       │   ├── left: Literal 1 [1:7]\n    \
+                 │       │   └── right: Binary * [1:13]\n    \
+                 │       │       ├── left: Literal 2 [1:11]\n    \
+                 │       │       └── right: Unary - [1:15]\n    \
+                 │       │           └── operand: Variable x [1:16]\n    \
+                 │       └── right: Grouping [1:21]\n    \
+                 │           └── expression: Literal 3 [1:22]\n    \
+                 └── Expression [2:1]\n        \
+                     └── expression: Call [2:1]\n            \
+                         ├── callee: Variable f [2:1]\n            \
+                         └── arguments\n                \
+                             ├── Variable a [2:3]\n                \
+                             └── Literal \"s\" [2:6]\n"
+        );
+        let tree = program(&check("a and\n  b;").unwrap());
+        assert!(tree.to_text().contains("expression: Logical and [1:3]"));
+    }
+
+    #[test]
+    fn missing_children_and_empty_lists() {
+        let tree = program(&check("fun f() { if (true) return; }").unwrap());
+        assert_eq!(
+            tree.to_text(),
+            "Program\n\
+             └── declarations\n    \
+                 └── Function f [1:5]\n        \
+                     ├── params: (none)\n        \
+                     └── body\n            \
+                         └── If [1:11]\n                \
+                             ├── condition: Literal true [1:15]\n                \
+                             └── then: Return [1:21]\n"
+        );
+        let (doc, _) = ast_json("if (true) print 1;");
+        assert!(doc.to_string().contains(r#""else":null"#));
+    }
+
+    #[test]
+    fn json_has_kinds_attributes_and_positions() {
+        let (doc, code) = ast_json("var a = !b;");
+        assert_eq!(code, 0);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"ast":{"kind":"Program","line":null,"column":null,"declarations":[{"kind":"Var","name":"a","line":1,"column":5,"initializer":{"kind":"Unary","operator":"!","line":1,"column":9,"operand":{"kind":"Variable","name":"b","line":1,"column":10}}}]},"diagnostics":[],"exit_code":0}"#
+        );
+        let (doc, code) = ast_json("var = 1;");
+        assert_eq!(code, 65);
+        assert!(doc.to_string().starts_with(r#"{"ast":null,"diagnostics":[{"kind":"parse""#));
+    }
+
+    #[test]
+    fn dot_graph_has_labelled_edges() {
+        let tree = program(&check("a.b = \"q\\\";").unwrap());
+        assert_eq!(
+            tree.to_dot(),
+            "digraph ast {\n    \
+                 node [shape=box, fontname=monospace];\n    \
+                 n0 [label=\"Program\"];\n    \
+                 n1 [label=\"Expression\\n1:1\"];\n    \
+                 n2 [label=\"Set\\nb\\n1:1\"];\n    \
+                 n3 [label=\"Variable\\na\\n1:1\"];\n    \
+                 n2 -> n3 [label=\"object\"];\n    \
+                 n4 [label=\"Literal\\n\\\"q\\\\\\\"\\n1:7\"];\n    \
+                 n2 -> n4 [label=\"value\"];\n    \
+                 n1 -> n2 [label=\"expression\"];\n    \
+                 n0 -> n1 [label=\"declarations[0]\"];\n\
+             }\n"
+        );
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod conformance_tests {
+    use std::fs;
+
+    use crate::conformance::{check, run_dir, Expectations};
+
+    #[test]
+    fn annotations_give_output_errors_and_exit_code() {
+        let source = "print 1; // expect: 1\n\
+                      print \"// expect: no\"; // expect: // expect: no\n\
+                      a; // expect runtime error: Undefined variable 'a'.\n\
+                      // [java line 7] Error at 'x': y\n\
+                      // [c line 7] Error at 'x': y\n\
+                      var; // Error at ';': Expect variable name.";
+        let e = Expectations::of(source);
+        assert_eq!(e.stdout, vec!["1", "// expect: no"]);
+        assert_eq!(e.runtime_error, Some(("Undefined variable 'a'.".to_string(), 3)));
+        assert_eq!(
+            e.errors,
+            vec!["[line 7] Error at x: y", "[line 6] Error at ;: Expect variable name."]
+        );
+        assert_eq!(e.exit_code(), 65);
+        assert_eq!(Expectations::of("// expect runtime error: x").exit_code(), 70);
+        assert_eq!(Expectations::of("print 1;").exit_code(), 0);
+    }
+
+    #[test]
+    fn passing_scripts_have_no_failures() {
+        assert!(check("print 1 + 2; // expect: 3").is_empty());
+        assert!(check("-\"a\"; // expect runtime error: Operand must be a number.").is_empty());
+        assert!(check("print 1;\nprint (; // Error at ;: Expect expression.").is_empty());
+    }
+
+    #[test]
+    fn upstream_error_annotations_match() {
+        // from test/variable/duplicate_local.lox of the upstream suite
+        let source = "{\n  var a = \"value\";\n  \
+                      var a = \"other\"; // Error at 'a': Already a variable with this name in this scope.\n\
+                      }";
+        assert_eq!(check(source), Vec::<String>::new());
+        let source = "// [line 2] Error at ')': Expect expression.\nprint );";
+        assert_eq!(check(source), Vec::<String>::new());
+    }
+
+    #[test]
+    fn failures_show_diffs() {
+        assert_eq!(
+            check("print 1; // expect: 1\nprint 2; // expect: 3"),
+            vec!["stdout differs:\n    1\n  - 3\n  + 2"]
+        );
+        let failures = check("print nil + 1; // expect: 1");
+        assert_eq!(failures.len(), 2);
+        assert_eq!(failures[0], "stdout differs:\n  - 1");
+        assert!(failures[1].starts_with("exit code: expected 0, got 70\n  stderr:\n    Operands"));
+    }
+
+    #[test]
+    fn runs_scripts_in_subdirectories() {
+        let dir = std::env::temp_dir().join(format!("lox-conformance-{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.lox"), "print 1; // expect: 1").unwrap();
+        fs::write(dir.join("sub/b.lox"), "print 1; // expect: 2").unwrap();
+        fs::write(dir.join("notes.txt"), "not a test").unwrap();
+        let mut out = Vec::new();
+        let passed = run_dir(&mut out, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(!passed);
+        let report = String::from_utf8(out).unwrap();
+        let lines = report.lines().collect::<Vec<_>>();
+        assert!(lines[0].starts_with("PASS ") && lines[0].ends_with("a.lox"));
+        assert!(lines[1].starts_with("FAIL ") && lines[1].ends_with("b.lox"));
+        assert_eq!(lines[2..], ["  stdout differs:", "  - 2", "  + 1", "1 passed, 1 failed"]);
+    }
+}
@@ -0,0 +1,93 @@
+#[cfg(test)]
+mod debugger_tests {
+    use crate::debugger::Debugger;
+    use crate::Interpreter;
+
+    const PROGRAM: &str = "fun add(a, b) {\n\
+                           \x20   var sum = a + b;\n\
+                           \x20   return sum;\n\
+                           }\n\
+                           var x = add(1, 2);\n\
+                           for (var i = 0; i < 2; i = i + 1) {\n\
+                           \x20   print i;\n\
+                           }\n\
+                           print x;\n";
+
+    /// program output, debugger output and exit status of session with given commands
+    fn session(commands: &str) -> (String, String, Option<u8>) {
+        let (mut out, mut debugger_out) = (Vec::new(), Vec::new());
+        let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+        interpreter.set_hook(Box::new(Debugger::new(
+            PROGRAM,
+            commands.as_bytes(),
+            &mut debugger_out,
+        )));
+        interpreter.run_source(PROGRAM).unwrap();
+        let status = interpreter.exit_status();
+        drop(interpreter);
+        (
+            String::from_utf8(out).unwrap(),
+            String::from_utf8(debugger_out).unwrap(),
+            status,
+        )
+    }
+
+    #[test]
+    fn step_enters_functions_and_next_steps_over_them() {
+        let (out, debug, _) = session("n\ns\ns\np a\np sum\nn\nn\np sum\n");
+        assert_eq!(out, "0\n1\n3\n");
+        assert_eq!(
+            debug,
+            "> 1 | fun add(a, b) {\n(debug) \
+             > 5 | var x = add(1, 2);\n(debug) \
+             > 2 |     var sum = a + b;\n(debug) \
+             > 3 |     return sum;\n(debug) \
+             a = 1\n(debug) \
+             sum = 3\n(debug) \
+             > 6 | for (var i = 0; i < 2; i = i + 1) {\n(debug) \
+             > 6 | for (var i = 0; i < 2; i = i + 1) {\n(debug) \
+             Undefined variable 'sum'.\n(debug) \n"
+        );
+    }
+
+    #[test]
+    fn breakpoints_pause_in_each_loop_iteration() {
+        let (out, debug, _) = session("b 7\nb 20\nc\np i\nc\nclear 7\nclear 7\nc\n");
+        assert_eq!(out, "0\n1\n3\n");
+        assert_eq!(
+            debug,
+            "> 1 | fun add(a, b) {\n(debug) \
+             Breakpoint at line 7.\n(debug) \
+             No line 20 in program.\n(debug) \
+             > 7 |     print i;\n(debug) \
+             i = 0\n(debug) \
+             > 7 |     print i;\n(debug) \
+             Deleted breakpoint at line 7.\n(debug) \
+             No breakpoint at line 7.\n(debug) "
+        );
+    }
+
+    #[test]
+    fn list_shows_current_line_in_context() {
+        let (_, debug, _) = session("n\nl\nbogus\n\nc\n");
+        assert_eq!(
+            debug,
+            "> 1 | fun add(a, b) {\n(debug) \
+             > 5 | var x = add(1, 2);\n(debug) \
+             \x20 3 |     return sum;\n  4 | }\n> 5 | var x = add(1, 2);\n\
+             \x20 6 | for (var i = 0; i < 2; i = i + 1) {\n  7 |     print i;\n(debug) \
+             Unknown command: bogus (try help)\n(debug) \
+             Unknown command: bogus (try help)\n(debug) "
+        );
+    }
+
+    #[test]
+    fn quit_stops_program() {
+        let (out, _, status) = session("b 9\nc\nq\nc\n");
+        assert_eq!(out, "0\n1\n");
+        assert_eq!(status, Some(0));
+        let (out, _, status) = session("");
+        assert_eq!(out, "0\n1\n3\n");
+        assert_eq!(status, None);
+    }
+}
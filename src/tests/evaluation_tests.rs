@@ -1,6 +1,5 @@
 /// This module tests evaluation cases in "cases.txt file".
 /// The file must include only one-liners (cases are read line by line)
-
 #[cfg(test)]
 struct Case {
     code: String,
@@ -8,16 +7,16 @@ struct Case {
 }
 
 #[cfg(test)]
-fn prepare<'a>(fname: &'a str) -> Vec<Case> {
+fn prepare(fname: &str) -> Vec<Case> {
     use crate::utils;
 
-    let c = utils::contents(fname);
+    let c = utils::contents(fname).unwrap();
     let mut cases = Vec::new();
     let lines = c.split("\n");
     for line in lines {
         let word_iter = line.split("\t").map(String::from).collect::<Vec<String>>();
-        eprint!("Word iter: {:?}\n", word_iter);
-        let case = Case { code: word_iter.get(0).unwrap().to_string(), evaluated: word_iter.get(1).unwrap().to_string().replace("\\n", "\n") };
+        eprintln!("Word iter: {:?}", word_iter);
+        let case = Case { code: word_iter.first().unwrap().to_string(), evaluated: word_iter.get(1).unwrap().to_string().replace("\\n", "\n") };
         cases.push(case);
     }
     cases
@@ -25,7 +24,7 @@ fn prepare<'a>(fname: &'a str) -> Vec<Case> {
 
 #[cfg(test)]
 fn run_case(c: Case) {
-    use crate::{evaluate_with_code, evaluator::StatementEvalResult};
+    use crate::{cli::evaluate_with_code, evaluator::StatementEvalResult};
 
     let (ve, opt_err, _code) = evaluate_with_code(&c.code);
 
@@ -39,6 +38,7 @@ fn run_case(c: Case) {
             StatementEvalResult::BlockResult(vec) => format!(
                 "{:?}", vec
             ),
+            other => format!("{:?}", other),
         };
         assert_eq!(c.evaluated,actual_expression_string, "Expected: {}, actual {}",  c.evaluated, actual_expression_string);
     }
@@ -0,0 +1,81 @@
+#[cfg(test)]
+mod formatter_tests {
+    use std::{fs, process::ExitCode};
+
+    use crate::ast_dump;
+    use crate::cli::{fmt, parse_with_code};
+    use crate::format_source;
+
+    fn format(source: &str) -> String {
+        format_source(source).unwrap()
+    }
+
+    #[test]
+    fn indents_blocks_and_spaces_operators() {
+        assert_eq!(
+            format("fun f( x,y ){if(x>y){return -x*(2+ -y);}else{return y;}}"),
+            "fun f(x, y) {\n    if (x > y) {\n        return -x * (2 + -y);\n    } else {\n        \
+             return y;\n    }\n}\n"
+        );
+        assert_eq!(
+            format("class A<B{init(){this.x=f(1,2).y;}}outer:for(;;){}"),
+            "class A < B {\n    init() {\n        this.x = f(1, 2).y;\n    }\n}\nouter: for (;;) {}\n"
+        );
+    }
+
+    #[test]
+    fn one_statement_per_line_without_rewriting_loops() {
+        assert_eq!(
+            format("var a=1;print a;for(var i=0;i<1;i=i+1)print i;"),
+            "var a = 1;\nprint a;\nfor (var i = 0; i < 1; i = i + 1) print i;\n"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_single_blank_lines() {
+        let source = "// head\nvar a = 1; // trailing\n\n\n\nvar b = 1 + // why\n2;\n\
+                      {\n\n/* in */ print a;\n\n}\nprint a; /* after */ print b;";
+        assert_eq!(
+            format(source),
+            "// head\nvar a = 1; // trailing\n\nvar b = 1 + // why\n    2;\n\
+             {\n    /* in */ print a;\n}\nprint a; /* after */\nprint b;\n"
+        );
+    }
+
+    #[test]
+    fn invalid_source_is_not_formatted() {
+        assert!(format_source("var a = ;").is_err());
+        assert!(format_source("print @;").is_err());
+    }
+
+    #[test]
+    fn formatting_is_idempotent_and_keeps_syntax_tree() {
+        for entry in fs::read_dir("src/tests").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|e| e == "lox") {
+                let source = fs::read_to_string(&path).unwrap();
+                let Ok(formatted) = format_source(&source) else {
+                    continue;
+                };
+                assert_eq!(format(&formatted), formatted, "in {}", path.display());
+                let ast = |s: &str| {
+                    let prog = parse_with_code(s).0;
+                    ast_dump::program(&prog).without_positions().to_json()
+                };
+                assert_eq!(ast(&formatted), ast(&source), "in {}", path.display());
+            }
+        }
+    }
+
+    #[test]
+    fn check_reports_unformatted_file() {
+        let path = std::env::temp_dir().join(format!("lox-fmt-{}.lox", std::process::id()));
+        let name = path.to_str().unwrap();
+        fs::write(&path, "print  1;").unwrap();
+        assert_eq!(fmt(name, true), ExitCode::FAILURE);
+        assert_eq!(fmt(name, false), ExitCode::SUCCESS);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "print 1;\n");
+        assert_eq!(fmt(name, true), ExitCode::SUCCESS);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,31 @@
+#[cfg(test)]
+mod input_tests {
+    use crate::utils::{contents, read_source, InputError};
+
+    #[test]
+    fn reads_utf8_source() {
+        assert_eq!(read_source("print \"é\";".as_bytes(), "x").unwrap(), "print \"é\";");
+    }
+
+    #[test]
+    fn invalid_utf8_is_located() {
+        let e = read_source(&b"print 1;\n\n\"\xc3\x28\";"[..], "bad.lox").unwrap_err();
+        assert!(matches!(e, InputError::InvalidUtf8 { offset: 11, line: 3, .. }));
+        assert_eq!(e.exit_code(), 65);
+        assert_eq!(
+            e.to_string(),
+            "bad.lox is not valid UTF-8: invalid byte at offset 11 (line 3)"
+        );
+    }
+
+    #[test]
+    fn unreadable_files_have_sysexits_codes() {
+        let missing = contents("src/tests/no-such-file.lox").unwrap_err();
+        assert!(matches!(missing, InputError::NoInput(..)));
+        assert_eq!(missing.exit_code(), 66);
+        assert!(missing.to_string().starts_with("Cannot open src/tests/no-such-file.lox: "));
+        let dir = contents("src/tests").unwrap_err();
+        assert!(matches!(dir, InputError::Io(..)));
+        assert_eq!(dir.exit_code(), 74);
+    }
+}
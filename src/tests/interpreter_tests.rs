@@ -0,0 +1,138 @@
+#[cfg(test)]
+mod interpreter_tests {
+    use crate::{
+        Interpreter, InterpreterError, LexicalError, ParseErrorKind, RuntimeErrorKind,
+    };
+
+    #[test]
+    fn run_source_writes_print_output() {
+        let mut out = Vec::new();
+        let res = Interpreter::with_writers(&mut out, std::io::sink()).run_source("print 1 + 2;");
+        assert!(res.is_ok());
+        assert_eq!(String::from_utf8(out).unwrap(), "3\n");
+    }
+
+    #[test]
+    fn globals_persist_between_runs() {
+        let mut interpreter = Interpreter::with_writers(std::io::sink(), std::io::sink());
+        interpreter.run_source("var a = 1;").unwrap();
+        interpreter.run_source("a = a + 1;").unwrap();
+        assert_eq!(interpreter.get_global("a").unwrap().to_string(), "2");
+        assert!(interpreter.get_global("b").is_none());
+    }
+
+    #[test]
+    fn errors_are_typed_by_phase() {
+        let mut interpreter = Interpreter::with_writers(std::io::sink(), std::io::sink());
+        match interpreter.run_source("print @;") {
+            Err(InterpreterError::Lex(v)) => {
+                assert_eq!(v.len(), 1);
+                assert_eq!(v[0].kind, LexicalError::UnknownToken('@'));
+                assert_eq!(v[0].to_string(), "[line 1] Error: Unexpected character: @");
+                let span = v[0].location.span;
+                assert_eq!((span.start, span.end, span.col), (6, 7, 7));
+            }
+            other => panic!("expected lex error, got {:?}", other),
+        }
+        match interpreter.run_source("return 1;") {
+            Err(e @ InterpreterError::Parse(_)) => assert_eq!(e.exit_code(), 65),
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        match interpreter.run_source("print (1;") {
+            Err(InterpreterError::Parse(v)) => {
+                assert_eq!(v[0].kind, ParseErrorKind::Expect("')' after expression".into()));
+                assert_eq!(v[0].code(), "E201");
+                assert_eq!(v[0].lexeme.as_deref(), Some(";"));
+            }
+            other => panic!("expected parse error, got {:?}", other),
+        }
+        match interpreter.run_source("-\"a\";") {
+            Err(InterpreterError::Runtime(e)) => {
+                assert_eq!(e.kind, RuntimeErrorKind::OperandMustBeNumber);
+                assert_eq!(e.code(), "E301");
+                assert_eq!(e.location.map(|l| l.line), Some(1));
+            }
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors_are_written_to_err_writer() {
+        let mut err = Vec::new();
+        let res = Interpreter::with_writers(std::io::sink(), &mut err).run_source("nope;");
+        assert!(res.is_err());
+        assert_eq!(
+            String::from_utf8(err).unwrap(),
+            "Undefined variable 'nope'.\n[Line 1]\n1 | nope;\n  | ^^^^"
+        );
+    }
+
+    #[test]
+    fn notes_follow_the_snippet() {
+        let mut err = Vec::new();
+        let res = Interpreter::with_writers(std::io::sink(), &mut err).run_source("print 1 + nil;");
+        assert!(res.is_err());
+        assert_eq!(
+            String::from_utf8(err).unwrap(),
+            "Operands must be two numbers or two strings.\n[Line 1]\n\
+             1 | print 1 + nil;\n  |       ^^^^^^^\n  = note: left operand is number, right operand is nil"
+        );
+    }
+
+    #[test]
+    fn exit_stops_program_with_its_code() {
+        let mut out = Vec::new();
+        let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+        let src = "fun f(n) { if (n == 0) exit(3); print n; f(n - 1); }\nf(2);\nprint 0;";
+        assert!(interpreter.run_source(src).is_ok());
+        assert_eq!(interpreter.exit_status(), Some(3));
+        interpreter.run_source("print 4;").unwrap();
+        assert_eq!(interpreter.exit_status(), None);
+        drop(interpreter);
+        assert_eq!(String::from_utf8(out).unwrap(), "2\n1\n4\n");
+    }
+
+    #[test]
+    fn exit_code_must_fit_a_byte() {
+        let mut interpreter = Interpreter::with_writers(std::io::sink(), std::io::sink());
+        for code in ["256", "-1", "1.5", "\"1\""] {
+            match interpreter.run_source(&format!("exit({});", code)) {
+                Err(e @ InterpreterError::Runtime(_)) => {
+                    assert_eq!(e.exit_code(), 70);
+                    assert!(e.to_string().starts_with("Exit code must be an integer"));
+                }
+                other => panic!("expected runtime error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn script_arguments_are_available() {
+        let mut out = Vec::new();
+        let mut interpreter = Interpreter::with_writers(&mut out, std::io::sink());
+        interpreter.run_source("print argc();").unwrap();
+        interpreter.set_args(vec!["a.lox".to_string(), "x".to_string()]);
+        interpreter.run_source("print argc(); print argv(0) + argv(1);").unwrap();
+        let err = interpreter.run_source("argv(2);").unwrap_err();
+        assert_eq!(err.to_string(), "Argument index must be an integer less than 2.\n[Line 1]");
+        drop(interpreter);
+        assert_eq!(String::from_utf8(out).unwrap(), "0\n2\na.loxx\n");
+    }
+
+    #[test]
+    fn deep_recursion_is_a_runtime_error() {
+        // runs on the default test thread stack, smaller than deepest allowed nesting needs
+        let mut interpreter = Interpreter::with_writers(std::io::sink(), std::io::sink());
+        let source = "fun d(n) { if (n == 0) return 0; { while (true) return 1 + d(n - 1); } }\n\
+                      var deep = d(990);";
+        interpreter.run_source(source).unwrap();
+        assert_eq!(interpreter.get_global("deep").unwrap().to_string(), "990");
+        match interpreter.run_source("fun f(n) { return n + f(n + 1); } f(0);") {
+            Err(e @ InterpreterError::Runtime(_)) => {
+                assert_eq!(e.exit_code(), 70);
+                assert!(e.to_string().starts_with("Stack overflow."));
+            }
+            other => panic!("expected runtime error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+#[cfg(test)]
+mod json_output_tests {
+    use crate::cli::{ast_json, evaluate_json, parse_json, run_json, tokenize_json};
+
+    #[test]
+    fn tokens_have_type_lexeme_literal_and_position() {
+        let (doc, code) = tokenize_json("x = 1.5");
+        assert_eq!(code, 0);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"tokens":[{"type":"IDENTIFIER","lexeme":"x","literal":null,"line":1,"column":1},{"type":"EQUAL","lexeme":"=","literal":null,"line":1,"column":3},{"type":"NUMBER","lexeme":"1.5","literal":1.5,"line":1,"column":5},{"type":"EOF","lexeme":"","literal":null,"line":1,"column":8}],"diagnostics":[],"exit_code":0}"#
+        );
+    }
+
+    #[test]
+    fn lex_errors_are_diagnostics() {
+        let (doc, code) = tokenize_json("\n  \"ab");
+        assert_eq!(code, 65);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"tokens":[{"type":"EOF","lexeme":"","literal":null,"line":2,"column":6}],"diagnostics":[{"kind":"lex","code":"E102","message":"Unterminated string.","line":2,"column":3,"note":"string literal is never closed with '\"'"}],"exit_code":65}"#
+        );
+    }
+
+    #[test]
+    fn ast_and_all_parse_errors() {
+        let (doc, code) = parse_json("print -a;\nvar;\n1 +;");
+        assert_eq!(code, 65);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"ast":{"kind":"Program","line":null,"column":null,"declarations":[{"kind":"Print","line":1,"column":1,"expression":{"kind":"Unary","operator":"-","line":1,"column":7,"operand":{"kind":"Variable","name":"a","line":1,"column":8}}},{"kind":"Invalid","line":null,"column":null},{"kind":"Expression","line":3,"column":1,"expression":{"kind":"Binary","operator":"+","line":3,"column":3,"left":{"kind":"Literal","value":1,"line":3,"column":1},"right":{"kind":"Invalid","line":null,"column":null}}}]},"diagnostics":[{"kind":"parse","code":"E201","message":"Expect variable name.","line":2,"column":4,"lexeme":";","note":null},{"kind":"parse","code":"E202","message":"Expect expression.","line":3,"column":4,"lexeme":";","note":null}],"exit_code":65}"#
+        );
+        let source = "outer: while (true) if (!(a)) break outer;";
+        assert_eq!(parse_json(source), ast_json(source));
+    }
+
+    #[test]
+    fn evaluation_results_with_types_and_output() {
+        let (doc, code) = evaluate_json("print \"hi\"; 1 < 2; nil;");
+        assert_eq!(code, 0);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"results":[{"type":"string","value":"hi"},{"type":"boolean","value":true},{"type":"nil","value":null}],"output":"hi\n","diagnostics":[],"exit_code":0}"#
+        );
+    }
+
+    #[test]
+    fn runtime_error_keeps_output_printed_before() {
+        let (doc, code) = run_json("print 1;\nprint -\"a\";", &[]);
+        assert_eq!(code, 70);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"output":"1\n","diagnostics":[{"kind":"runtime","code":"E301","message":"Operand must be a number.","line":2,"column":8,"note":null}],"exit_code":70}"#
+        );
+    }
+}
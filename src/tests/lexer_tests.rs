@@ -73,7 +73,7 @@ fn parses_unary_minus() {
 #[cfg(test)]
 fn assert_token_vec_lexing_result(s: &str, expected: Vec<TokenType>) {
     assert_eq!(
-        Lexer::new(&s)
+        Lexer::new(s)
             .tokens()
             .iter()
             .map(|t| t.typ.clone())
@@ -277,3 +277,101 @@ fn parse_ident_unterm() {
 fn reserved() {
     compare("for fun", "FOR for null\nFUN fun null\nEOF  null");
 }
+
+#[test]
+fn reserved_loop_control() {
+    compare(
+        "outer: break continue",
+        "IDENTIFIER outer null\nCOLON : null\nBREAK break null\nCONTINUE continue null\nEOF  null",
+    );
+}
+
+#[test]
+fn tokens_carry_byte_range_and_column() {
+    let spans = Lexer::new("var a = \"x\";\n  a // note\n != 1.5")
+        .tokens()
+        .iter()
+        .map(|t| (t.span.start, t.span.end, t.span.col))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        spans,
+        vec![
+            (0, 3, 1),
+            (4, 5, 5),
+            (6, 7, 7),
+            (8, 11, 9),
+            (11, 12, 12),
+            (15, 16, 3),
+            (26, 28, 2),
+            (29, 32, 5),
+            (32, 32, 8),
+        ]
+    );
+}
+
+#[test]
+fn column_counts_characters_not_bytes() {
+    let ts = Lexer::new("\"żółw\" + x").tokens();
+    let plus = &ts[1];
+    assert_eq!((plus.span.start, plus.span.end, plus.span.col), (10, 11, 8));
+}
+
+#[test]
+fn column_is_counted_along_long_lines_and_after_multiline_strings() {
+    let line = "ż + ".repeat(10_000);
+    let ts = Lexer::new(&format!("{}x \"a\nbc\" y", line)).tokens();
+    let cols = ts.iter().rev().take(4).map(|t| (t.ln, t.span.col)).collect::<Vec<_>>();
+    assert_eq!(cols, vec![(2, 6), (2, 5), (2, 40_003), (1, 40_001)]);
+}
+
+#[test]
+fn nested_block_comments_are_skipped_and_count_lines() {
+    let ts = Lexer::new("/* a /* b\n */ c */ x\n/**/ y").tokens();
+    let found = ts.iter().map(|t| (t.s.as_str(), t.ln)).collect::<Vec<_>>();
+    assert_eq!(found, vec![("x", 2), ("y", 3), ("", 3)]);
+}
+
+#[test]
+fn unterminated_block_comment_reports_starting_line() {
+    compare(
+        "1\n/* a /* b */\n\n",
+        "NUMBER 1 1.0\n[line 2] Error: Unterminated block comment.\nEOF  null",
+    );
+}
+
+#[test]
+fn comments_are_kept_as_trivia() {
+    use crate::{trivia, TriviaKind};
+
+    let found = trivia("/// adds\nfun f() {} // done\n//// rule\n/* x\n */")
+        .into_iter()
+        .map(|t| (t.kind, t.ln, t.text))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            (TriviaKind::DocComment, 1, " adds".to_string()),
+            (TriviaKind::LineComment, 2, " done".to_string()),
+            (TriviaKind::LineComment, 3, "// rule".to_string()),
+            (TriviaKind::BlockComment, 4, " x\n ".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn blank_lines_are_kept_as_trivia() {
+    use crate::{trivia, TriviaKind};
+
+    let found = trivia("a;\n\n  \nb; // c\n\"\n\n\"")
+        .into_iter()
+        .map(|t| (t.kind, t.ln, t.text, t.span.start))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            (TriviaKind::BlankLine, 2, "".to_string(), 3),
+            (TriviaKind::BlankLine, 3, "  ".to_string(), 4),
+            (TriviaKind::LineComment, 4, " c".to_string(), 10),
+        ]
+    );
+}
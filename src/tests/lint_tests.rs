@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod lint_tests {
+    use std::process::ExitCode;
+
+    use crate::cli::{lint_json, lintw};
+    use crate::linter::lint;
+    use crate::{InterpreterError, WarningKind};
+
+    /// codes and lines of warnings for source
+    fn codes(source: &str) -> Vec<(&'static str, u64)> {
+        lint(source)
+            .unwrap()
+            .iter()
+            .map(|w| (w.code(), w.location.line))
+            .collect()
+    }
+
+    #[test]
+    fn reports_unused_and_shadowing_locals() {
+        let source = "var a = 1;\n{\n  var a = 2;\n  var b = a;\n  var _c;\n}\nvar g;";
+        assert_eq!(codes(source), vec![("W002", 3), ("W001", 4)]);
+        let w = lint(source).unwrap();
+        assert_eq!(w[1].kind, WarningKind::UnusedVariable("b".into()));
+        assert_eq!(w[1].to_string(), "[line 4] Warning W001: Local variable 'b' is never used.");
+    }
+
+    #[test]
+    fn closures_and_loops_use_locals() {
+        let source = "fun f() { var x = 1; fun g() { return x; } return g; }\n\
+                      for (var i = 0; i < 2; i = i + 1) print i;";
+        assert!(codes(source).is_empty());
+    }
+
+    #[test]
+    fn reports_useless_statements_and_comparisons() {
+        let source = "var a;\n1 + 2;\na;\nf();\na = a;\nprint 1 == \"1\";\nprint nil != false;";
+        assert_eq!(
+            codes(source.replace("f();", "clock();").as_str()),
+            vec![("W003", 2), ("W003", 3), ("W004", 5), ("W005", 6), ("W005", 7)]
+        );
+        let w = lint("print 1 != true;").unwrap();
+        assert_eq!(
+            w[0].kind.to_string(),
+            "Comparison of number with boolean is always true."
+        );
+    }
+
+    #[test]
+    fn reports_property_self_assignment() {
+        let source = "class A { m() { this.x = this.x; this.x = this.y; } }";
+        let w = lint(source).unwrap();
+        assert_eq!(w.len(), 1);
+        assert_eq!(w[0].kind, WarningKind::SelfAssignment("this.x".into()));
+    }
+
+    #[test]
+    fn directives_suppress_warnings_by_code_or_name() {
+        let source = "{\n  // lint: allow W001\n  var a;\n  var b; // lint: allow no-effect, unused-variable\n  var c;\n}";
+        assert_eq!(codes(source), vec![("W001", 5)]);
+    }
+
+    #[test]
+    fn errors_prevent_linting() {
+        assert!(matches!(lint("var;"), Err(InterpreterError::Parse(_))));
+        let mut err = Vec::new();
+        assert_eq!(lintw(&mut err, "return 1;", false), ExitCode::from(65));
+    }
+
+    #[test]
+    fn warnings_fail_only_when_denied() {
+        let mut err = Vec::new();
+        assert_eq!(lintw(&mut err, "1;", false), ExitCode::SUCCESS);
+        assert_eq!(
+            String::from_utf8(err).unwrap(),
+            "[line 1] Warning W003: Expression statement has no effect.\n1 | 1;\n  | ^\n"
+        );
+        assert_eq!(lintw(&mut Vec::new(), "1;", true), ExitCode::from(65));
+        assert_eq!(lintw(&mut Vec::new(), "print 1;", true), ExitCode::SUCCESS);
+        let (doc, code) = lint_json("1;", true);
+        assert_eq!(code, 65);
+        assert_eq!(
+            doc.to_string(),
+            r#"{"diagnostics":[{"kind":"lint","code":"W003","message":"Expression statement has no effect.","line":1,"column":1,"name":"no-effect"}],"exit_code":65}"#
+        );
+    }
+}
@@ -1,6 +1,17 @@
+#![allow(clippy::module_inception)]
+mod ast_dump_tests;
+mod conformance_tests;
+mod debugger_tests;
 mod evaluation_tests;
+mod formatter_tests;
+mod input_tests;
+mod interpreter_tests;
+mod json_output_tests;
 #[cfg(test)]
 mod lexer_tests;
+mod lint_tests;
 mod parser_tests;
+mod repl_tests;
 mod run_tests;
 mod tests_main;
+mod unparser_tests;
@@ -108,3 +108,191 @@ fn parses_equality_operator2() {
         "(> (! (group (== baz baz))) 5.0)",
     )
 }
+
+#[test]
+fn parses_if_else() {
+    assert_parsed_text_result("if (true) 1; else 2;", "(if true 1.0 2.0)")
+}
+
+#[test]
+fn parses_dangling_else_with_nearest_if() {
+    assert_parsed_text_result(
+        "if (true) if (false) 1; else 2;",
+        "(if true (if false 1.0 2.0))",
+    )
+}
+
+#[test]
+fn parses_while() {
+    assert_parsed_text_result("while (false) 1;", "(while false 1.0)")
+}
+
+#[test]
+fn parses_for_desugared_to_while() {
+    assert_parsed_text_result(
+        "for (var i = 0; i < 3; i = i + 1) print i;",
+        "(block var i = 0.0; (while (< (var IDENTIFIER i null) 3.0) (block (var IDENTIFIER i null) (IDENTIFIER i null = (+ (var IDENTIFIER i null) 1.0)))))",
+    )
+}
+
+#[test]
+fn parses_continued_for_with_body_in_loop_run_once() {
+    assert_parsed_text_result(
+        "for (;; i) { while (a) continue; if (b) continue; break; }",
+        "for_1: (while true (block for_1_body: (while true (block (block (while (var IDENTIFIER a null) (continue)) (if (var IDENTIFIER b null) (break for_1_body)) (break for_1)) (break for_1_body))) (var IDENTIFIER i null)))",
+    );
+    assert_parsed_text_result(
+        "for_1: for (;;) for (;;) continue for_1;",
+        "for_1: (while true for_2_body: (while true (block (while true (break for_2_body)) (break for_2_body))))",
+    )
+}
+
+#[test]
+fn parses_for_without_clauses() {
+    assert_parsed_text_result("for (;;) 1;", "(while true 1.0)")
+}
+
+#[test]
+fn parses_labeled_loop_with_break_and_continue() {
+    assert_parsed_text_result(
+        "outer: while (true) { continue outer; break; }",
+        "outer: (while true (block (continue outer) (break)))",
+    )
+}
+
+#[test]
+fn parses_or_looser_than_and() {
+    assert_parsed_text_result(
+        "true or false and nil",
+        "(or true (and false nil))",
+    )
+}
+
+#[test]
+fn parses_and_looser_than_equality() {
+    assert_parsed_text_result(
+        "1 == 2 and 3 != 4 or 5 < 6",
+        "(or (and (== 1.0 2.0) (!= 3.0 4.0)) (< 5.0 6.0))",
+    )
+}
+
+#[test]
+fn parses_call_with_arguments() {
+    assert_parsed_text_result("f(1, 2)(3)", "(call (call (var IDENTIFIER f null) 1.0 2.0) 3.0)")
+}
+
+#[test]
+fn parses_fun_declaration() {
+    assert_parsed_text_result(
+        "fun add(a, b) { return a + b; }",
+        "(fun add (a b) (block (return (+ (var IDENTIFIER a null) (var IDENTIFIER b null)))))",
+    )
+}
+
+#[test]
+fn parses_property_get_and_set() {
+    assert_parsed_text_result(
+        "a.b.c = this.d",
+        "(set (get (var IDENTIFIER a null) b) c (get this d))",
+    )
+}
+
+#[test]
+fn parses_super_method_access() {
+    assert_parsed_text_result("super.speak()", "(call (super speak))")
+}
+
+#[test]
+fn expression_span_covers_its_tokens() {
+    use crate::{lexer, parser::{Decl, Parser, Stmt}};
+
+    let src = "print a.b(1) + c;";
+    let prog = Parser::new(lexer::Lexer::new(src).tokens()).parse();
+    let span = match &prog.declarations[0] {
+        Decl::Statement(Stmt::Print(_, e)) => e.span().unwrap(),
+        other => panic!("expected print statement, got {}", other),
+    };
+    assert_eq!(&src[span.start..span.end], "a.b(1) + c");
+    assert_eq!(span.col, 7);
+}
+
+#[test]
+fn spans_include_keywords_prefix_operators_and_parens() {
+    use crate::{lexer, parser::Parser};
+
+    let src = "var a = -(b);\nprint !(a);";
+    let prog = Parser::new(lexer::Lexer::new(src).tokens()).parse();
+    let spans = prog
+        .declarations
+        .iter()
+        .map(|d| d.span().map(|s| &src[s.start..s.end]))
+        .collect::<Vec<_>>();
+    assert_eq!(spans, vec![Some("a = -(b)"), Some("print !(a)")]);
+}
+
+#[test]
+fn recovers_at_statement_boundaries() {
+    use crate::{lexer, parser::Parser, ParseErrorKind};
+
+    let src = "print (1;\nvar = 2;\n{ print ; print 3; }\nprint 4";
+    let prog = Parser::new(lexer::Lexer::new(src).tokens()).parse();
+    let errors = prog
+        .syntax_errors()
+        .iter()
+        .map(|e| (e.location.line, e.kind.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (1, ParseErrorKind::Expect("')' after expression".into())),
+            (2, ParseErrorKind::Expect("variable name".into())),
+            (3, ParseErrorKind::ExpectExpression),
+        ]
+    );
+    assert_eq!(prog.declarations.last().unwrap().to_string(), "4.0");
+}
+
+#[test]
+fn leaves_unexpected_token_to_next_statement() {
+    use crate::{lexer, parser::Parser, ParseErrorKind};
+
+    let prog = Parser::new(lexer::Lexer::new("print (; fun;").tokens()).parse();
+    let errors = prog
+        .syntax_errors()
+        .iter()
+        .map(|e| (e.lexeme.clone(), e.kind.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        errors,
+        vec![
+            (Some(";".into()), ParseErrorKind::ExpectExpression),
+            (Some(";".into()), ParseErrorKind::Expect("function name".into())),
+        ]
+    );
+}
+
+#[test]
+fn too_deep_nesting_is_a_syntax_error() {
+    use crate::{lexer, parser::Parser, Interpreter, ParseErrorKind};
+
+    // parsed on the default test thread stack
+    let deep = 20_000;
+    for src in [
+        format!("print {}1{};", "(".repeat(deep), ")".repeat(deep)),
+        format!("print {}1;", "!".repeat(deep)),
+        format!("a = {}1;", "a = ".repeat(deep)),
+        format!("{}{}", "{".repeat(deep), "}".repeat(deep)),
+        format!("{}1;", "if (true) ".repeat(deep)),
+        format!("{}{}", "fun f() {".repeat(deep), "}".repeat(deep)),
+    ] {
+        let prog = Parser::new(lexer::Lexer::new(&src).tokens()).parse();
+        // recovery may report unbalanced closing tokens after the first error
+        let first = prog.syntax_errors().first().map(|e| e.kind.clone());
+        assert_eq!(first, Some(ParseErrorKind::TooMuchNesting), "{}", &src[..20]);
+    }
+
+    let src = format!("print {}1{};", "-(".repeat(44), ")".repeat(44));
+    let mut out = Vec::new();
+    Interpreter::with_writers(&mut out, std::io::sink()).run_source(&src).unwrap();
+    assert_eq!(out, b"1\n");
+}
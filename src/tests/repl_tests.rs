@@ -0,0 +1,61 @@
+#[cfg(test)]
+mod repl_tests {
+    use crate::repl::{is_incomplete, run};
+
+    /// stdout and stderr of session reading input
+    fn session(input: &str) -> (String, String) {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        assert_eq!(run(input.as_bytes(), &mut out, &mut err).unwrap(), 0);
+        (String::from_utf8(out).unwrap(), String::from_utf8(err).unwrap())
+    }
+
+    #[test]
+    fn prints_values_of_bare_expressions_only() {
+        let (out, err) = session("var a = 1;\na + 1\nfun f() {}\nprint a;\nnil;\n");
+        assert_eq!(out, "> > 2\n> > 1\n> nil\n> \n");
+        assert_eq!(err, "");
+    }
+
+    #[test]
+    fn continues_input_while_brackets_are_open() {
+        let (out, _) = session("fun f(x) {\n  return x *\n 2;\n}\nf(\n2)\n");
+        assert_eq!(out, "> ... ... ... > ... 4\n> \n");
+        assert!(is_incomplete("print \"a\n"));
+        assert!(is_incomplete("/* a"));
+        assert!(!is_incomplete("{ }"));
+        assert!(!is_incomplete(")"));
+    }
+
+    #[test]
+    fn keeps_definitions_after_errors() {
+        let (out, err) = session("var a = 1;\nprint b;\nvar = ;\na\n// comment only\n");
+        assert_eq!(out, "> > > > 1\n> > \n");
+        assert_eq!(
+            err,
+            "Undefined variable 'b'.\n[Line 1]\n1 | print b;\n  |       ^\n\
+             [line 1] Error at =: Expect variable name.\n1 | var = ;\n  |     ^\n"
+        );
+    }
+
+    #[test]
+    fn missing_semicolon_is_added_after_last_token() {
+        let (out, err) = session("1 + 1 // two\n1 +\n");
+        assert_eq!(out, "> 2\n> > \n");
+        assert_eq!(err, "[line 1] Error at ;: Expect expression.\n1 | 1 +;\n  |    ^\n");
+    }
+
+    #[test]
+    fn exit_ends_session_with_its_code() {
+        let (mut out, mut err) = (Vec::new(), Vec::new());
+        let code = run("1 + 1\nexit(4)\nprint 5;\n".as_bytes(), &mut out, &mut err).unwrap();
+        assert_eq!(code, 4);
+        assert_eq!(String::from_utf8(out).unwrap(), "> 2\n> ");
+    }
+
+    #[test]
+    fn continues_after_stack_overflow() {
+        let (out, err) = session("fun f() { f(); }\nf();\n1 + 1\n");
+        assert_eq!(out, "> > > 2\n> \n");
+        assert!(err.starts_with("Stack overflow."));
+    }
+}
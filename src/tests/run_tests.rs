@@ -2,7 +2,7 @@
 mod run_tests {
     use std::fs;
      use colored::Colorize;
-    use crate::runw;
+    use crate::cli::runw;
 
     #[derive(Debug, Clone)]
     struct FileCase {
@@ -22,15 +22,15 @@ mod run_tests {
 
         fn update_case(&mut self, case_suffix: String, p: std::borrow::Cow<'_, str>) {
             let data = &fs::read_to_string(p.as_ref())
-                .expect(format!("Should be abe to read from {}", p).as_str());
+                .unwrap_or_else(|_| panic!("Should be abe to read from {}", p));
 
             match case_suffix.as_str() {
                 "lox" => self.run_code.push_str(data),
                 "out" => self.expected.push_str(data),
                 _ => (),
             }
-            eprint!(
-                "update case {:?}: case_name:{}, case suffix: {} ,data: {}\n",
+            eprintln!(
+                "update case {:?}: case_name:{}, case suffix: {} ,data: {}",
                 self, self.fname, case_suffix, data
             );
         }
@@ -51,16 +51,16 @@ mod run_tests {
 
                 let case_split = p.split(".").collect::<Vec<_>>();
                 let case_name = case_split
-                    .get(0)
-                    .expect(format!("Extract file name should suceed with {}", p).as_str())
+                    .first()
+                    .unwrap_or_else(|| panic!("Extract file name should suceed with {}", p))
                     .to_string();
                 let case_suffix = case_split
                     .get(1)
-                    .expect(format!("Extract file name should suceed with {}", p).as_str())
+                    .unwrap_or_else(|| panic!("Extract file name should suceed with {}", p))
                     .to_string();
-                if vec!["lox".to_string(), "out".to_string()].contains(&case_suffix) {
-                    eprint!(
-                        "split: {:?}, pat: {:?}, case_name: {}, case_suffix: {}\n",
+                if ["lox".to_string(), "out".to_string()].contains(&case_suffix) {
+                    eprintln!(
+                        "split: {:?}, pat: {:?}, case_name: {}, case_suffix: {}",
                         case_split,
                         pat.to_str(),
                         case_name,
@@ -69,7 +69,7 @@ mod run_tests {
                     map.entry(case_name)
                         .or_insert(FileCase::new())
                         .update_case(case_suffix, p);
-                    eprint!("Map len: {:?}\n", map.len());
+                    eprintln!("Map len: {:?}", map.len());
                 }
             }
         };
@@ -91,7 +91,7 @@ mod run_tests {
     #[test]
     fn run_all_lox_and_out_files() {
         if let Ok(v) = prepare("src/tests") {
-            let mut copy = Vec::from(v);
+            let mut copy = v;
             copy.sort_by_key(|fc|fc.fname.clone());
             for el in copy {
                 eprint!("Test {}", el.fname);
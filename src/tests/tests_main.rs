@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests_main {
 
-    use crate::*;
+    use crate::cli::{evaluate_with_code, parse_with_code};
+    use crate::evaluator::StatementEvalResult;
 
     #[test]
     fn parse_with_exit_0() {
@@ -36,6 +37,21 @@ mod tests_main {
             outp: "false",
             code: 0,
         },
+        Case {
+            inp: "!0",
+            outp: "false",
+            code: 0,
+        },
+        Case {
+            inp: "!\"\"",
+            outp: "false",
+            code: 0,
+        },
+        Case {
+            inp: "!!nil",
+            outp: "false",
+            code: 0,
+        },
         Case {
             inp: "!10.40",
             outp: "false",
@@ -99,12 +115,97 @@ mod tests_main {
         },
         Case {
             inp: " 234h ",
-            outp: "Undefined variable 'h'.",
+            outp: "Undefined variable 'h'.\n[Line 1]",
             code: 70,
         },
         Case {
             inp: " if 3",
-            outp: "Invalid expresstion: [line 1] Error at if: Expected primary (number,  string, bool, nil)  or left paren",
+            outp: "[line 1] Error at 3: Expect '(' after 'if'.",
+            code: 65,
+        },
+        Case {
+            inp: "if (1 < 2) print \"then\"; else print \"else\";",
+            outp: "then",
+            code: 0,
+        },
+        Case {
+            inp: "if (nil) 1; else 2;",
+            outp: "2",
+            code: 0,
+        },
+        Case {
+            inp: "if (true) print 1",
+            outp: "1",
+            code: 0,
+        },
+        Case {
+            inp: "if (true print 1;",
+            outp: "[line 1] Error at print: Expect ')' after if condition.",
+            code: 65,
+        },
+        Case {
+            inp: "while (true print 1;",
+            outp: "[line 1] Error at print: Expect ')' after condition.",
+            code: 65,
+        },
+        Case {
+            inp: "for (var i = 0; i < 1) print i;",
+            outp: "[line 1] Error at ): Expect ';' after loop condition.",
+            code: 65,
+        },
+        Case {
+            inp: "break;",
+            outp: "[line 1] Error at break: Can't use 'break' outside of a loop.",
+            code: 65,
+        },
+        Case {
+            inp: "if (true) { continue; }",
+            outp: "[line 1] Error at continue: Can't use 'continue' outside of a loop.",
+            code: 65,
+        },
+        Case {
+            inp: "outer: while (true) { while (true) break inner; }",
+            outp: "[line 1] Error at inner: No enclosing loop labeled 'inner'.",
+            code: 65,
+        },
+        Case {
+            inp: "outer: print 1;",
+            outp: "[line 1] Error at print: Expect loop after label.",
+            code: 65,
+        },
+        Case {
+            inp: "while (true) { fun f() { break; } }",
+            outp: "[line 1] Error at break: Can't use 'break' outside of a loop.",
+            code: 65,
+        },
+        Case {
+            inp: "fun (a) {}",
+            outp: "[line 1] Error at (: Expect function name.",
+            code: 65,
+        },
+        Case {
+            inp: "fun f(a, 1) {}",
+            outp: "[line 1] Error at 1: Expect parameter name.",
+            code: 65,
+        },
+        Case {
+            inp: "fun f() {} f(1);",
+            outp: "Expected 0 arguments but got 1.\n[Line 1]",
+            code: 70,
+        },
+        Case {
+            inp: "fun f() { return 42; } f();",
+            outp: "<fn f>",
+            code: 0,
+        },
+        Case {
+            inp: "fun f(a, a) {}",
+            outp: "[line 1] Error at a: Already a variable with this name in this scope.",
+            code: 65,
+        },
+        Case {
+            inp: "{ var a = 1; var a = 2; }\n{ var b = b; }",
+            outp: "[line 1] Error at a: Already a variable with this name in this scope.\n[line 2] Error at b: Can't read local variable in its own initializer.",
             code: 65,
         },
         Case {
@@ -114,14 +215,14 @@ mod tests_main {
     {
         print world + baz;
 }"#,
-            outp: "[line 6] Error at end: Expect '}'",
+            outp: "[line 6] Error at end: Expect '}' after block.",
             code: 65
         }
     ];
 
         for c in cases {
             let (eres, opt_error, actual_code) = evaluate_with_code(c.inp);
-            eprint!("|Err: {:?} | Input: {} | Expr: {:?} \n", opt_error, c.inp, eres);
+            eprintln!("|Err: {:?} | Input: {} | Expr: {:?} ", opt_error, c.inp, eres);
             assert_eq!(
                 actual_code, c.code,
                 "Expected code: {}, got: {} in {:?}",
@@ -135,7 +236,8 @@ mod tests_main {
                 let actual_expression_string: String = match eval_result {
                     StatementEvalResult::PrintStatementResult(fin) => fin.to_string(),
                     StatementEvalResult::ExpressionStatementResult(fin) => fin.to_string(),
-                    StatementEvalResult::BlockResult(vec) => format!("{:?}", vec)
+                    StatementEvalResult::BlockResult(vec) => format!("{:?}", vec),
+                    other => format!("{:?}", other),
                 };
                 assert_eq!(
                     c.outp, actual_expression_string,
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod unparser_tests {
+    use std::fs;
+
+    use crate::ast_dump;
+    use crate::cli::parse_with_code;
+    use crate::json::Json;
+    use crate::unparse_source;
+
+    /// syntax tree without token positions
+    fn ast(source: &str) -> Json {
+        let (prog, code) = parse_with_code(source);
+        assert_eq!(code, 0, "syntax error in {}", source);
+        ast_dump::program(&prog).without_positions().to_json()
+    }
+
+    fn assert_round_trip(source: &str) -> String {
+        let printed = unparse_source(source).unwrap();
+        assert_eq!(ast(&printed), ast(source), "printed as:\n{}", printed);
+        printed
+    }
+
+    #[test]
+    fn parenthesises_only_where_needed() {
+        assert_eq!(assert_round_trip("print (1+2)*3;"), "print (1 + 2) * 3;\n");
+        assert_eq!(assert_round_trip("print 1-(2-3);"), "print 1 - (2 - 3);\n");
+        assert_eq!(assert_round_trip("print 1-2-3;"), "print 1 - 2 - 3;\n");
+        assert_eq!(
+            assert_round_trip("var a;var b;a=b=!-1<=2==true or false and nil;"),
+            "var a;\nvar b;\na = b = !-1 <= 2 == true or false and nil;\n"
+        );
+        assert_eq!(
+            assert_round_trip("class A{init(){this.x=this.f(1,\"s\").y;}}"),
+            "class A {\n    init() {\n        this.x = this.f(1, \"s\").y;\n    }\n}\n"
+        );
+    }
+
+    #[test]
+    fn prints_statements_and_loops() {
+        let source = "fun f(a,b){if(a)return b;else{return;}}\n\
+                      outer: for(var i=0;i<2;i=i+1){for(;;){break outer;}}\n\
+                      while(true)continue;\n\
+                      class B<A{} {}";
+        let expected = "fun f(a, b) {\n    if (a) return b; else {\n        return;\n    }\n}\n\
+                        {\n    var i = 0;\n    outer: while (i < 2) {\n        {\n            \
+                        while (true) {\n                {\n                    break outer;\n                \
+                        }\n                nil;\n            }\n        }\n        i = i + 1;\n    }\n}\n\
+                        while (true) continue;\n\
+                        class B < A {}\n{}\n";
+        assert_eq!(
+            unparse_source(&source.replace("for(;;)", "for(;;nil)")).unwrap(),
+            expected
+        );
+        assert_round_trip(source);
+        assert_round_trip("var i = 0; while (i < 2) { i = i + 1; }");
+        assert_round_trip("for (var i = 0; i < 2; i = i + 1) { if (i) continue; break; }");
+    }
+
+    #[test]
+    fn else_stays_with_its_if() {
+        let source = "if (true) { if (false) print 1; } else print 2;";
+        assert_eq!(
+            assert_round_trip(source),
+            "if (true) {\n    if (false) print 1;\n} else print 2;\n"
+        );
+        assert_round_trip("if (1) if (2) print 1; else print 2;");
+    }
+
+    #[test]
+    fn test_programs_round_trip() {
+        let mut checked = 0;
+        for entry in fs::read_dir("src/tests").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|e| e == "lox") {
+                let source = fs::read_to_string(&path).unwrap();
+                if unparse_source(&source).is_ok() {
+                    assert_round_trip(&source);
+                    checked += 1;
+                }
+            }
+        }
+        assert!(checked > 30);
+    }
+}
@@ -2,10 +2,11 @@ use crate::lexer::LineNum;
 use std::{fmt::Display, str::FromStr};
 
 #[derive(PartialEq, Eq, Clone, Debug)]
-pub(crate) enum LexicalError {
+pub enum LexicalError {
     UnknownToken(char),
     UnterminatedString,
     InvalidNumber,
+    UnterminatedBlockComment,
 }
 impl Display for LexicalError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -13,13 +14,14 @@ impl Display for LexicalError {
             Self::UnknownToken(c) => f.write_fmt(format_args!("Unexpected character: {}", c)),
             Self::UnterminatedString => f.write_str("Unterminated string."),
             Self::InvalidNumber => f.write_str("Invalid number."),
+            Self::UnterminatedBlockComment => f.write_str("Unterminated block comment."),
         }
     }
 }
 
 /// newtype for f64 to be used in Token::Number
 #[derive(Clone, PartialEq, Debug)]
-pub(crate) struct Numeric(pub f64);
+pub struct Numeric(pub f64);
 impl Display for Numeric {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("{:?}", self.0))
@@ -35,7 +37,7 @@ impl FromStr for Numeric {
 }
 /// Lex language token
 #[derive(PartialEq, Clone, Debug)]
-pub(crate) enum TokenType {
+pub enum TokenType {
     LeftParen,
     RightParen,
     LeftBrace,
@@ -46,6 +48,7 @@ pub(crate) enum TokenType {
     Plus,
     Minus,
     Semicolon,
+    Colon,
     Equal,
     EqualEqual,
     BangEqual,
@@ -62,7 +65,9 @@ pub(crate) enum TokenType {
     Eof,
     /* Reserved words */
     And,
+    Break,
     Class,
+    Continue,
     Else,
     False,
     For,
@@ -79,14 +84,68 @@ pub(crate) enum TokenType {
     While,
 }
 
+impl TokenType {
+    /// upper case name used in token listings, e.g. LEFT_PAREN
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LeftParen => "LEFT_PAREN",
+            Self::RightParen => "RIGHT_PAREN",
+            Self::LeftBrace => "LEFT_BRACE",
+            Self::RightBrace => "RIGHT_BRACE",
+            Self::Star => "STAR",
+            Self::Dot => "DOT",
+            Self::Comma => "COMMA",
+            Self::Plus => "PLUS",
+            Self::Minus => "MINUS",
+            Self::Semicolon => "SEMICOLON",
+            Self::Colon => "COLON",
+            Self::Equal => "EQUAL",
+            Self::EqualEqual => "EQUAL_EQUAL",
+            Self::BangEqual => "BANG_EQUAL",
+            Self::Bang => "BANG",
+            Self::LessEqual => "LESS_EQUAL",
+            Self::Less => "LESS",
+            Self::GreaterEqual => "GREATER_EQUAL",
+            Self::Greater => "GREATER",
+            Self::Unknown(_) => "UNKNOWN",
+            Self::StringLiteral => "STRING",
+            Self::Number(_) => "NUMBER",
+            Self::Identifier => "IDENTIFIER",
+            Self::Slash => "SLASH",
+            Self::Eof => "EOF",
+            Self::And => "AND",
+            Self::Break => "BREAK",
+            Self::Class => "CLASS",
+            Self::Continue => "CONTINUE",
+            Self::Else => "ELSE",
+            Self::False => "FALSE",
+            Self::For => "FOR",
+            Self::Fun => "FUN",
+            Self::If => "IF",
+            Self::Nil => "NIL",
+            Self::Or => "OR",
+            Self::Print => "PRINT",
+            Self::Return => "RETURN",
+            Self::Super => "SUPER",
+            Self::This => "THIS",
+            Self::True => "TRUE",
+            Self::Var => "VAR",
+            Self::While => "WHILE",
+        }
+    }
+}
+
 impl Display for TokenType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::And => f.write_str("AND and null"),
             Self::Bang => f.write_str("BANG ! null"),
             Self::BangEqual => f.write_str("BANG_EQUAL != null"),
+            Self::Break => f.write_str("BREAK break null"),
             Self::Class => f.write_str("CLASS class null"),
+            Self::Colon => f.write_str("COLON : null"),
             Self::Comma => f.write_str("COMMA , null"),
+            Self::Continue => f.write_str("CONTINUE continue null"),
             Self::Dot => f.write_str("DOT . null"),
             Self::Else => f.write_str("ELSE else null"),
             Self::Eof => f.write_str("EOF  null"),
@@ -126,28 +185,87 @@ impl Display for TokenType {
     }
 }
 
+/// Location of source fragment: byte range start..end
+/// and 1-based column (in characters) of its first character.
+/// Tokens not read from source (e.g. created by the evaluator) have default span with column 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub col: usize,
+}
+
+impl Span {
+    /// false for default span of tokens not read from source
+    pub fn is_known(&self) -> bool {
+        self.col > 0
+    }
+
+    /// smallest span covering both spans
+    pub fn to(self, other: Span) -> Span {
+        if other.start < self.start {
+            return other.to(self);
+        }
+        Span {
+            start: self.start,
+            end: self.end.max(other.end),
+            col: self.col,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
-pub(crate) struct Token {
+pub struct Token {
     /// Type of token
     pub typ: TokenType,
     /// line number where the token was seen
     pub ln: LineNum,
     /// parsed input fragment
     pub s: String,
+    /// where the token was seen in source
+    pub span: Span,
 }
 impl Token {
     pub fn new(typ: TokenType, ln: LineNum, s: String) -> Token {
-        Token { typ, ln, s }
-    }
-    pub(crate) fn of_char(typ: TokenType, ln: LineNum, c: char) -> Token {
         Token {
             typ,
             ln,
-            s: c.to_string(),
+            s,
+            span: Span::default(),
         }
     }
-    
+    pub(crate) fn of_char(typ: TokenType, ln: LineNum, c: char) -> Token {
+        Token::new(typ, ln, c.to_string())
+    }
+
+    pub(crate) fn with_span(self, span: Span) -> Token {
+        Token { span, ..self }
+    }
 }
+/// Kind of source fragment skipped by the lexer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TriviaKind {
+    /// `// ...`
+    LineComment,
+    /// `/// ...` documenting the declaration below it
+    DocComment,
+    /// `/* ... */`, possibly nested
+    BlockComment,
+    /// line containing only whitespace
+    BlankLine,
+}
+
+/// Comment or blank line skipped by the lexer, kept for tooling
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    /// line where the comment starts
+    pub ln: LineNum,
+    /// comment text without its delimiters (whitespace of blank line)
+    pub text: String,
+    pub span: Span,
+}
+
 impl Display for Token {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.typ {
@@ -0,0 +1,288 @@
+/// Prints syntax tree back as Lox source. Parsing the output gives an equal tree
+/// (up to token positions) for any program without syntax errors.
+/// For loops are printed as the while loops the parser lowers them to.
+///
+/// Blocks are indented by four spaces, one declaration per line. Parentheses are
+/// printed for Expression::Paren and where precedence of operators requires them.
+use crate::{
+    interpreter::{check, InterpreterError},
+    parser::{Binary, Class, Decl, Expression, Function, Logical, Program, Stmt, Unary},
+    token::{Token, TokenType},
+};
+
+const INDENT: &str = "    ";
+
+/// Source of program, one top level declaration per line
+pub(crate) fn unparse(p: &Program) -> String {
+    let mut u = Unparser::default();
+    for d in p.declarations.iter() {
+        u.line(d);
+    }
+    u.out
+}
+
+/// Lox source parsed and printed back by the unparser
+pub fn unparse_source(source: &str) -> Result<String, InterpreterError> {
+    Ok(unparse(&check(source)?))
+}
+
+/// Precedence levels of expressions, from loosest binding
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum Precedence {
+    Assignment,
+    Or,
+    And,
+    Equality,
+    Comparison,
+    Term,
+    Factor,
+    Unary,
+    Call,
+    Primary,
+}
+
+impl Precedence {
+    fn of(e: &Expression) -> Self {
+        match e {
+            Expression::Assign(..) | Expression::Set(..) => Self::Assignment,
+            Expression::LogicalEx(_, Logical::Or, ..) => Self::Or,
+            Expression::LogicalEx(_, Logical::And, ..) => Self::And,
+            Expression::BinaryEx(_, op, ..) => Self::of_binary(op),
+            Expression::UnaryEx(..) => Self::Unary,
+            Expression::Call(..) | Expression::Get(..) => Self::Call,
+            Expression::Primary(_)
+            | Expression::Variable(..)
+            | Expression::This(..)
+            | Expression::Super(..)
+            | Expression::Paren(..)
+            | Expression::Invalid => Self::Primary,
+        }
+    }
+
+    fn of_binary(op: &Binary) -> Self {
+        match op {
+            Binary::EqualEqual | Binary::NotEqual | Binary::Invalid(_) => Self::Equality,
+            Binary::Less | Binary::LessEqual | Binary::Greater | Binary::GreaterEqual => {
+                Self::Comparison
+            }
+            Binary::Plus | Binary::Minus => Self::Term,
+            Binary::Multiply | Binary::Divide => Self::Factor,
+        }
+    }
+
+    /// next tighter binding level (for right operands of left associative operators)
+    fn next(self) -> Self {
+        match self {
+            Self::Assignment => Self::Or,
+            Self::Or => Self::And,
+            Self::And => Self::Equality,
+            Self::Equality => Self::Comparison,
+            Self::Comparison => Self::Term,
+            Self::Term => Self::Factor,
+            Self::Factor => Self::Unary,
+            Self::Unary => Self::Call,
+            Self::Call | Self::Primary => Self::Primary,
+        }
+    }
+}
+
+fn literal(t: &Token) -> String {
+    match t.typ {
+        TokenType::StringLiteral => format!("\"{}\"", t.s),
+        _ => t.s.clone(),
+    }
+}
+
+/// text of expression, parenthesised if it binds looser than min
+fn expr(e: &Expression, min: Precedence) -> String {
+    let text = match e {
+        Expression::Primary(t) => literal(t),
+        Expression::Variable(t, _) => t.s.clone(),
+        Expression::This(..) => "this".to_string(),
+        Expression::Super(_, method, _) => format!("super.{}", method.s),
+        Expression::Paren(_, e, _) => format!("({})", expr(e, Precedence::Assignment)),
+        Expression::Assign(t, v, _) => format!("{} = {}", t.s, expr(v, Precedence::Assignment)),
+        Expression::Set(o, name, v) => format!(
+            "{}.{} = {}",
+            expr(o, Precedence::Call),
+            name.s,
+            expr(v, Precedence::Assignment)
+        ),
+        Expression::BinaryEx(l, op, _, r) => {
+            let p = Precedence::of_binary(op);
+            let op = match op {
+                Binary::Invalid(t) => t.s.clone(),
+                op => op.to_string(),
+            };
+            format!("{} {} {}", expr(l, p), op, expr(r, p.next()))
+        }
+        Expression::LogicalEx(l, op, _, r) => {
+            let p = Precedence::of(e);
+            format!("{} {} {}", expr(l, p), op, expr(r, p.next()))
+        }
+        Expression::UnaryEx(op, _, operand) => {
+            let op = match op {
+                Unary::Invalid(t) => t.s.clone(),
+                op => op.to_string(),
+            };
+            format!("{}{}", op, expr(operand, Precedence::Unary))
+        }
+        Expression::Call(c, _, args) => {
+            let args = args
+                .iter()
+                .map(|a| expr(a, Precedence::Assignment))
+                .collect::<Vec<_>>();
+            format!("{}({})", expr(c, Precedence::Call), args.join(", "))
+        }
+        Expression::Get(o, name) => format!("{}.{}", expr(o, Precedence::Call), name.s),
+        Expression::Invalid => "/* invalid */".to_string(),
+    };
+    if Precedence::of(e) < min {
+        format!("({})", text)
+    } else {
+        text
+    }
+}
+
+/// statement ends with if statement without else, which would take
+/// an else branch following it
+fn takes_else(s: &Stmt) -> bool {
+    match s {
+        Stmt::If(_, _, _, None) => true,
+        Stmt::If(_, _, _, Some(e)) => takes_else(e),
+        Stmt::While(_, _, body, ..) => takes_else(body),
+        _ => false,
+    }
+}
+
+#[derive(Default)]
+struct Unparser {
+    out: String,
+    /// depth of blocks enclosing current declaration
+    indent: usize,
+}
+
+impl Unparser {
+    fn pad(&mut self) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    /// declaration on its own line
+    fn line(&mut self, d: &Decl) {
+        self.pad();
+        self.decl(d);
+        self.out.push('\n');
+    }
+
+    fn decl(&mut self, d: &Decl) {
+        match d {
+            Decl::VarDecl(t, None) => self.out.push_str(&format!("var {};", t.s)),
+            Decl::VarDecl(t, Some(e)) => {
+                let init = expr(e, Precedence::Assignment);
+                self.out.push_str(&format!("var {} = {};", t.s, init));
+            }
+            Decl::Fun(f) => {
+                self.out.push_str("fun ");
+                self.function(f);
+            }
+            Decl::Class(c) => self.class(c),
+            Decl::Statement(s) => self.stmt(s),
+        }
+    }
+
+    fn function(&mut self, f: &Function) {
+        let params = f.params.iter().map(|p| p.s.as_str()).collect::<Vec<_>>();
+        self.out.push_str(&format!("{}({}) ", f.name.s, params.join(", ")));
+        self.block(&f.body);
+    }
+
+    fn class(&mut self, c: &Class) {
+        self.out.push_str(&format!("class {}", c.name.s));
+        if let Some(sc) = &c.superclass {
+            self.out.push_str(&format!(" < {}", expr(sc, Precedence::Primary)));
+        }
+        if c.methods.is_empty() {
+            self.out.push_str(" {}");
+            return;
+        }
+        self.out.push_str(" {\n");
+        self.indent += 1;
+        for m in c.methods.iter() {
+            self.pad();
+            self.function(m);
+            self.out.push('\n');
+        }
+        self.indent -= 1;
+        self.pad();
+        self.out.push('}');
+    }
+
+    fn block(&mut self, v: &[Decl]) {
+        if v.is_empty() {
+            self.out.push_str("{}");
+            return;
+        }
+        self.out.push_str("{\n");
+        self.indent += 1;
+        v.iter().for_each(|d| self.line(d));
+        self.indent -= 1;
+        self.pad();
+        self.out.push('}');
+    }
+
+    fn stmt(&mut self, s: &Stmt) {
+        match s {
+            Stmt::Print(_, e) => {
+                let e = expr(e, Precedence::Assignment);
+                self.out.push_str(&format!("print {};", e));
+            }
+            Stmt::Expression(e) => {
+                let e = expr(e, Precedence::Assignment);
+                self.out.push_str(&format!("{};", e));
+            }
+            Stmt::Block(v) => self.block(v),
+            Stmt::If(_, c, t, e) => {
+                self.out.push_str(&format!("if ({}) ", expr(c, Precedence::Assignment)));
+                match e {
+                    Some(e) if takes_else(t) => {
+                        self.block(&[Decl::Statement(t.as_ref().clone())]);
+                        self.out.push_str(" else ");
+                        self.stmt(e);
+                    }
+                    Some(e) => {
+                        self.stmt(t);
+                        self.out.push_str(" else ");
+                        self.stmt(e);
+                    }
+                    None => self.stmt(t),
+                }
+            }
+            Stmt::While(_, c, b, l) => {
+                self.label(l);
+                self.out.push_str(&format!("while ({}) ", expr(c, Precedence::Assignment)));
+                self.stmt(b);
+            }
+            Stmt::Break(_, l) => self.loop_control("break", l),
+            Stmt::Continue(_, l) => self.loop_control("continue", l),
+            Stmt::Return(_, None) => self.out.push_str("return;"),
+            Stmt::Return(_, Some(e)) => {
+                let e = expr(e, Precedence::Assignment);
+                self.out.push_str(&format!("return {};", e));
+            }
+            Stmt::Invalid => self.out.push_str("/* invalid */"),
+        }
+    }
+
+    fn label(&mut self, l: &Option<Token>) {
+        if let Some(l) = l {
+            self.out.push_str(&format!("{}: ", l.s));
+        }
+    }
+
+    fn loop_control(&mut self, keyword: &str, l: &Option<Token>) {
+        match l {
+            Some(l) => self.out.push_str(&format!("{} {};", keyword, l.s)),
+            None => self.out.push_str(&format!("{};", keyword)),
+        }
+    }
+}
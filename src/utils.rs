@@ -1,6 +1,81 @@
-use std::fs;
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    io::{self, Read},
+};
 
-pub(crate) fn contents(file_path: &str) -> String {
-   fs::read_to_string(file_path)
-        .expect("Should have been able to read the file")
-}
\ No newline at end of file
+/// sysexits code for input file that does not exist or is not readable
+pub const NO_INPUT_CODE: u8 = 66;
+/// sysexits code for error while reading input
+pub const IO_ERROR_CODE: u8 = 74;
+/// sysexits code for input data that is not valid (here: not UTF-8)
+pub const DATA_ERROR_CODE: u8 = 65;
+
+/// Failure to read Lox source
+#[derive(Debug)]
+pub enum InputError {
+    /// file does not exist or may not be read
+    NoInput(String, io::Error),
+    Io(String, io::Error),
+    /// first invalid byte: its offset and line (1-based)
+    InvalidUtf8 { name: String, offset: usize, line: usize },
+}
+
+impl InputError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Self::NoInput(..) => NO_INPUT_CODE,
+            Self::Io(..) => IO_ERROR_CODE,
+            Self::InvalidUtf8 { .. } => DATA_ERROR_CODE,
+        }
+    }
+}
+
+impl Error for InputError {}
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoInput(name, e) => write!(f, "Cannot open {}: {}", name, e),
+            Self::Io(name, e) => write!(f, "Cannot read {}: {}", name, e),
+            Self::InvalidUtf8 { name, offset, line } => write!(
+                f,
+                "{} is not valid UTF-8: invalid byte at offset {} (line {})",
+                name, offset, line
+            ),
+        }
+    }
+}
+
+/// Source read from input named name (for error messages)
+pub fn read_source(mut input: impl Read, name: &str) -> Result<String, InputError> {
+    let mut bytes = Vec::new();
+    input
+        .read_to_end(&mut bytes)
+        .map_err(|e| InputError::Io(name.to_string(), e))?;
+    String::from_utf8(bytes).map_err(|e| {
+        let offset = e.utf8_error().valid_up_to();
+        let line = e.as_bytes()[..offset].iter().filter(|b| **b == b'\n').count() + 1;
+        InputError::InvalidUtf8 {
+            name: name.to_string(),
+            offset,
+            line,
+        }
+    })
+}
+
+/// Contents of file, or of standard input for path `-`
+pub fn contents(file_path: &str) -> Result<String, InputError> {
+    if file_path == "-" {
+        return read_source(io::stdin().lock(), "<stdin>");
+    }
+    match fs::File::open(file_path) {
+        Ok(file) => read_source(file, file_path),
+        Err(e) => match e.kind() {
+            io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => {
+                Err(InputError::NoInput(file_path.to_string(), e))
+            }
+            _ => Err(InputError::Io(file_path.to_string(), e)),
+        },
+    }
+}